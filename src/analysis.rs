@@ -0,0 +1,297 @@
+//! Post-game analysis: every move re-scored with a deeper search and compared
+//! with the best move in its position.
+
+use crate::board::Disc;
+use crate::cpu::{self, SearchLimits, DEFAULT_TT_SIZE_MB};
+use crate::eval::Evaluator;
+use crate::game::{Game, GameResult, Move};
+use crate::tt::TranspositionTable;
+
+pub const DEFAULT_ANALYSIS_DEPTH: u32 = 8;
+
+/// How costly a move was, by the discs it gave away against the best move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Judgement {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// Losses of at least 2, 4 and 8 discs are inaccuracies, mistakes and
+    /// blunders.
+    pub fn from_loss(loss: f64) -> Self {
+        if loss >= 8.0 {
+            Judgement::Blunder
+        } else if loss >= 4.0 {
+            Judgement::Mistake
+        } else if loss >= 2.0 {
+            Judgement::Inaccuracy
+        } else if loss > 0.0 {
+            Judgement::Good
+        } else {
+            Judgement::Best
+        }
+    }
+
+    /// The usual annotation: `?!`, `?` or `??`, and nothing for sound moves.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Judgement::Best | Judgement::Good => "",
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+/// The analysis of one move. Scores are final disc margins for the side that
+/// moved, exact once the endgame is solved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveAnalysis {
+    /// Index of the move in the game history.
+    pub ply: usize,
+    pub player: Disc,
+    pub played: Move,
+    pub played_score: f64,
+    /// The best move found; the played move when it scores as well.
+    pub best: Move,
+    pub best_score: f64,
+    pub exact: bool,
+}
+
+impl MoveAnalysis {
+    /// Discs given away against the best move.
+    pub fn loss(&self) -> f64 {
+        (self.best_score - self.played_score).max(0.0)
+    }
+
+    pub fn judgement(&self) -> Judgement {
+        Judgement::from_loss(self.loss())
+    }
+
+    /// The score after the move as a margin for Black.
+    pub fn black_score(&self) -> f64 {
+        if self.player == Disc::Black { self.played_score } else { -self.played_score }
+    }
+}
+
+/// Totals for one side of an analyzed game.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SideSummary {
+    pub moves: u32,
+    pub total_loss: f64,
+    pub inaccuracies: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+}
+
+impl SideSummary {
+    pub fn average_loss(&self) -> f64 {
+        if self.moves == 0 { 0.0 } else { self.total_loss / self.moves as f64 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameAnalysis {
+    /// One entry per move placed, in order; passes are left out.
+    pub moves: Vec<MoveAnalysis>,
+    /// The result, if the game was finished.
+    pub result: Option<GameResult>,
+}
+
+impl GameAnalysis {
+    /// The analysis of the move at `ply` in the game history, unless it was a
+    /// pass.
+    pub fn at_ply(&self, ply: usize) -> Option<&MoveAnalysis> {
+        self.moves.binary_search_by_key(&ply, |analysis| analysis.ply).ok().map(|index| &self.moves[index])
+    }
+
+    pub fn summary(&self, player: Disc) -> SideSummary {
+        let mut summary = SideSummary::default();
+        for analysis in self.moves.iter().filter(|analysis| analysis.player == player) {
+            summary.moves += 1;
+            summary.total_loss += analysis.loss();
+            match analysis.judgement() {
+                Judgement::Inaccuracy => summary.inaccuracies += 1,
+                Judgement::Mistake => summary.mistakes += 1,
+                Judgement::Blunder => summary.blunders += 1,
+                Judgement::Best | Judgement::Good => {}
+            }
+        }
+        summary
+    }
+
+    /// Black's expected final margin after each analyzed move.
+    pub fn black_scores(&self) -> Vec<f64> {
+        self.moves.iter().map(MoveAnalysis::black_score).collect()
+    }
+}
+
+/// Scores every move of `game` against the alternatives with [`cpu::rank_moves`].
+/// `on_move` is called with the number of moves done and the total after each
+/// one, e.g. to show progress, and returns `false` to stop early; the analysis
+/// then covers the moves done so far.
+pub fn analyze(
+    game: &Game,
+    limits: &SearchLimits,
+    evaluator: &dyn Evaluator,
+    mut on_move: impl FnMut(usize, usize) -> bool,
+) -> GameAnalysis {
+    let mut table = TranspositionTable::new(DEFAULT_TT_SIZE_MB);
+    let (board, side) = game.start_position();
+    let mut position = Game::from_position(board, side);
+    let total = game.history().iter().filter(|record| record.mv != Move::Pass).count();
+    let mut moves = Vec::with_capacity(total);
+    for (ply, record) in game.history().iter().enumerate() {
+        if let Move::Place(row, col) = record.mv {
+            let ranked = cpu::rank_moves(&position, limits, evaluator, &mut table);
+            let played = ranked.iter().find(|result| result.best_move == (row, col)).expect("the played move is legal");
+            // Prefer the played move among equally good ones.
+            let best = if played.score == ranked[0].score { played } else { &ranked[0] };
+            moves.push(MoveAnalysis {
+                ply,
+                player: record.player,
+                played: record.mv,
+                played_score: played.disc_margin(),
+                best: Move::Place(best.best_move.0, best.best_move.1),
+                best_score: best.disc_margin(),
+                exact: played.is_exact(),
+            });
+            if !on_move(moves.len(), total) {
+                break;
+            }
+        }
+        position.play(record.mv);
+    }
+    GameAnalysis { moves, result: game.result() }
+}
+
+/// Draws `scores` as a column chart, one column per score: `height` rows above
+/// an axis for positive scores and `height` below it for negative ones, top
+/// row first. Returns the rows and the score a full column stands for, the
+/// smallest of 4, 8, 16, 32 and 64 discs that fits every score.
+pub fn score_graph(scores: &[f64], height: usize) -> (Vec<String>, f64) {
+    let largest = scores.iter().fold(0.0f64, |largest, score| largest.max(score.abs()));
+    let scale = [4.0, 8.0, 16.0, 32.0].into_iter().find(|scale| largest <= *scale).unwrap_or(64.0);
+    let height = height.max(1);
+    let row = |filled: &dyn Fn(f64) -> bool| -> String {
+        scores.iter().map(|&score| if filled(score) { '█' } else { ' ' }).collect()
+    };
+    let mut rows = Vec::with_capacity(2 * height + 1);
+    for level in (0..height).rev() {
+        let threshold = scale * level as f64 / height as f64;
+        rows.push(row(&|score| score > threshold));
+    }
+    rows.push("─".repeat(scores.len()));
+    for level in 0..height {
+        let threshold = scale * level as f64 / height as f64;
+        rows.push(row(&|score| score < -threshold));
+    }
+    (rows, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::Weights;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn test_judgement() {
+        assert_eq!(Judgement::from_loss(0.0), Judgement::Best);
+        assert_eq!(Judgement::from_loss(1.5), Judgement::Good);
+        assert_eq!(Judgement::from_loss(2.0), Judgement::Inaccuracy);
+        assert_eq!(Judgement::from_loss(6.0), Judgement::Mistake);
+        assert_eq!(Judgement::from_loss(20.0), Judgement::Blunder);
+        assert_eq!(Judgement::Blunder.symbol(), "??");
+        assert!(Judgement::Mistake > Judgement::Inaccuracy);
+    }
+
+    #[test]
+    fn test_analyze_solved_endgame() {
+        // The last ten moves of a Medium (Black) against Easy game, all solved exactly.
+        let position = "XXXOOOO--XXXXO--OXXOXXXX-X-XXOXXXXXXXOXX-XOOOXXXX-XXXOXX-XXX-XOX X";
+        let mut game = Game::from_position_str(position).unwrap();
+        game.play_transcript("h1e8g2h2c4a6b7a2a4a8").unwrap();
+        let weights = Weights::builtin();
+        let mut progress = Vec::new();
+        let analysis = analyze(&game, &SearchLimits::depth(4), weights.as_ref(), |done, total| {
+            progress.push((done, total));
+            true
+        });
+        assert_eq!(progress.last(), Some(&(10, 10)));
+        assert!(analysis.moves.iter().all(|analysis| analysis.exact));
+
+        let judgements: Vec<Judgement> = analysis.moves.iter().map(MoveAnalysis::judgement).collect();
+        use Judgement::*;
+        assert_eq!(judgements, [Blunder, Best, Inaccuracy, Inaccuracy, Best, Mistake, Best, Blunder, Best, Best]);
+        // h1 threw away a draw (e8 scores 0).
+        assert_eq!(analysis.moves[0].best, "e8".parse().unwrap());
+        assert_eq!((analysis.moves[0].played_score, analysis.moves[0].loss()), (-10.0, 10.0));
+        assert_eq!(analysis.black_scores().last(), Some(&10.0));
+        assert_eq!(analysis.result.map(|result| result.margin(Disc::Black)), Some(10));
+
+        let white = analysis.summary(Disc::White);
+        assert_eq!((white.moves, white.inaccuracies, white.mistakes, white.blunders), (5, 1, 1, 1));
+        assert_eq!(white.average_loss(), 22.0 / 5.0);
+        assert_eq!(analysis.at_ply(7).map(|analysis| analysis.played), Some("a2".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_analyze_opening() {
+        let game = Game::from_transcript("f5d6c3d3c4").unwrap();
+        let weights = Weights::builtin();
+        let analysis = analyze(&game, &SearchLimits::depth(3), weights.as_ref(), |_, _| true);
+        assert_eq!(analysis.moves.len(), 5);
+        assert_eq!(analysis.result, None);
+        for (ply, analysis) in analysis.moves.iter().enumerate() {
+            assert_eq!(analysis.ply, ply);
+            assert!(analysis.best_score >= analysis.played_score);
+            assert_eq!(analysis.played, game.history()[ply].mv);
+        }
+        // The first move is symmetric: every choice is best.
+        assert_eq!(analysis.moves[0].judgement(), Judgement::Best);
+        assert_eq!(analysis.moves[0].best, analysis.moves[0].played);
+        let black = analysis.summary(Disc::Black);
+        assert_eq!(black.moves, 3);
+        assert_eq!(analysis.black_scores()[1], -analysis.moves[1].played_score);
+
+        let stopped = analyze(&game, &SearchLimits::depth(3), weights.as_ref(), |done, _| done < 2);
+        assert_eq!(stopped.moves, analysis.moves[..2]);
+    }
+
+    #[test]
+    fn test_analyze_cancelled_from_another_thread() {
+        let game = Game::from_transcript("f5d6c3d3c4f4f6f3e6e7").unwrap();
+        let weights = Weights::builtin();
+        let cancelled = AtomicBool::new(false);
+        // Unbuffered, so the first progress report waits until the flag is set.
+        let (sender, receiver) = mpsc::sync_channel(0);
+        let analysis = thread::scope(|scope| {
+            let worker = scope.spawn(|| {
+                analyze(&game, &SearchLimits::depth(3), weights.as_ref(), |done, _| {
+                    let _ = sender.send(done);
+                    !cancelled.load(Ordering::Relaxed)
+                })
+            });
+            cancelled.store(true, Ordering::Relaxed);
+            assert_eq!(receiver.recv(), Ok(1));
+            worker.join().unwrap()
+        });
+        assert_eq!(analysis.moves.len(), 1);
+    }
+
+    #[test]
+    fn test_score_graph() {
+        let (rows, scale) = score_graph(&[3.0, -1.0, 0.0, -4.0], 2);
+        assert_eq!(scale, 4.0);
+        assert_eq!(rows, ["█   ", "█   ", "────", " █ █", "   █"]);
+        assert_eq!(score_graph(&[40.0], 1).1, 64.0);
+        assert_eq!(score_graph(&[], 3).0.len(), 7);
+    }
+}
@@ -0,0 +1,290 @@
+//! Headless games between players, and statistics over a match.
+
+use crate::board::Disc;
+use crate::clock::{Clock, TimeControl};
+use crate::game::{Game, GameResult, Move};
+use crate::player::Player;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+/// What is known about one move of a headless game, e.g. for GGF records.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MoveNote {
+    /// The mover's evaluation as a final disc margin, if the player reported one.
+    pub eval: Option<f64>,
+    /// Time spent choosing the move; `None` for forced passes and opening moves.
+    pub time: Option<Duration>,
+}
+
+/// How a headless game ended.
+#[derive(Debug, Clone)]
+pub struct GameOutcome {
+    pub game: Game,
+    pub result: GameResult,
+    /// The side that lost by failing to move, playing an illegal move or running
+    /// out of time. Forfeits count as a 64-0 loss.
+    pub forfeit: Option<Disc>,
+    /// One note per move of `game.history()`.
+    pub notes: Vec<MoveNote>,
+}
+
+/// Plays `game` to the end. Forced passes are played automatically.
+pub fn play_game(mut game: Game, black: &mut dyn Player, white: &mut dyn Player, clock: &Clock) -> GameOutcome {
+    let mut clock = clock.clone();
+    let mut notes = vec![MoveNote::default(); game.history().len()];
+    while !game.is_game_over() {
+        if game.must_pass() {
+            game.play(Move::Pass);
+            notes.push(MoveNote::default());
+            continue;
+        }
+        let side = game.current_turn();
+        let player: &mut dyn Player = if side == Disc::Black { &mut *black } else { &mut *white };
+        let start = Instant::now();
+        let mv = player.choose_move(&game, &clock);
+        let elapsed = start.elapsed();
+        // Only a total time control is strict; per-move budgets are advisory.
+        let flagged = !clock.spend(side, elapsed) && matches!(clock.control(), TimeControl::Total { .. });
+        match mv {
+            Some(mv) if !flagged && game.play(mv) => {
+                notes.push(MoveNote { eval: player.last_eval(), time: Some(elapsed) });
+            }
+            _ => return GameOutcome { notes, ..forfeit(game, side) },
+        }
+    }
+    let result = game.result().expect("game is over");
+    GameOutcome { game, result, forfeit: None, notes }
+}
+
+/// Ends `game` as a loss for `side`.
+pub fn forfeit(game: Game, side: Disc) -> GameOutcome {
+    let result = if side == Disc::Black {
+        GameResult { winner: Some(Disc::White), black: 0, white: 64 }
+    } else {
+        GameResult { winner: Some(Disc::Black), black: 64, white: 0 }
+    };
+    let notes = vec![MoveNote::default(); game.history().len()];
+    GameOutcome { game, result, forfeit: Some(side), notes }
+}
+
+/// A game after `plies` random legal moves (fewer if the game ends first).
+pub fn random_opening<R: Rng + ?Sized>(plies: u32, rng: &mut R) -> Game {
+    let mut game = Game::new();
+    for _ in 0..plies {
+        let moves = game.legal_moves();
+        if moves.is_empty() {
+            break;
+        }
+        game.play(moves[rng.random_range(0..moves.len())]);
+    }
+    game
+}
+
+/// Wins, draws and losses from the first player's point of view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Sum of final disc differentials.
+    pub margin_total: i64,
+}
+
+/// An Elo difference with the half-width of its 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EloEstimate {
+    pub elo: f64,
+    pub margin: f64,
+}
+
+impl MatchStats {
+    /// Adds a game that the first player finished `margin` discs ahead.
+    pub fn record(&mut self, margin: i32) {
+        match margin.signum() {
+            1 => self.wins += 1,
+            0 => self.draws += 1,
+            _ => self.losses += 1,
+        }
+        self.margin_total += margin as i64;
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, counting a draw as half a win.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    pub fn average_margin(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        self.margin_total as f64 / self.games() as f64
+    }
+
+    /// The Elo difference implied by the score, or `None` when one side won every
+    /// game and the difference is unbounded. The interval comes from the standard
+    /// error of the per-game score; it is infinite when it reaches a 0% or 100%
+    /// score.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        let n = self.games() as f64;
+        let score = self.score();
+        if self.games() == 0 || score <= 0.0 || score >= 1.0 {
+            return None;
+        }
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / n;
+        let error = 1.96 * (variance / n).sqrt();
+        let (low, high) = (score - error, score + error);
+        let margin = if low <= 0.0 || high >= 1.0 { f64::INFINITY } else { (elo_from_score(high) - elo_from_score(low)) / 2.0 };
+        Some(EloEstimate { elo: elo_from_score(score), margin })
+    }
+}
+
+fn elo_from_score(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchConfig {
+    pub games: u32,
+    /// Random plies played before each pair of games; 0 starts from the initial
+    /// position.
+    pub opening_plies: u32,
+    /// Makes the random openings reproducible.
+    pub seed: Option<u64>,
+    pub clock: Clock,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig { games: 10, opening_plies: 0, seed: None, clock: Clock::unlimited() }
+    }
+}
+
+/// Plays `config.games` games between `first` and `second`, alternating colours.
+/// Each random opening is played twice, once with each player as Black.
+/// `on_game` sees every game as it finishes, with its index and whether `first`
+/// played Black.
+pub fn run_match(
+    first: &mut dyn Player,
+    second: &mut dyn Player,
+    config: &MatchConfig,
+    mut on_game: impl FnMut(u32, &GameOutcome, bool),
+) -> MatchStats {
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    let mut stats = MatchStats::default();
+    let mut opening = Game::new();
+    for index in 0..config.games {
+        let first_is_black = index % 2 == 0;
+        if first_is_black {
+            opening = random_opening(config.opening_plies, &mut rng);
+        }
+        let outcome = if first_is_black {
+            play_game(opening.clone(), first, second, &config.clock)
+        } else {
+            play_game(opening.clone(), second, first, &config.clock)
+        };
+        let first_side = if first_is_black { Disc::Black } else { Disc::White };
+        stats.record(outcome.result.margin(first_side));
+        on_game(index, &outcome, first_is_black);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::{CpuConfig, SearchLimits};
+    use crate::player::{CpuLevel, CpuPlayer};
+
+    struct Resigner;
+
+    impl Player for Resigner {
+        fn name(&self) -> String {
+            "Resigner".to_string()
+        }
+
+        fn choose_move(&mut self, _game: &Game, _clock: &Clock) -> Option<Move> {
+            None
+        }
+    }
+
+    fn cpu(level: CpuLevel) -> CpuPlayer {
+        CpuPlayer::new(level).with_config(CpuConfig { book: None, seed: Some(3), ..CpuConfig::default() })
+    }
+
+    #[test]
+    fn test_play_game_to_the_end() {
+        let outcome = play_game(Game::new(), &mut cpu(CpuLevel::Easy), &mut cpu(CpuLevel::Medium), &Clock::unlimited());
+        assert!(outcome.game.is_game_over());
+        assert_eq!(outcome.forfeit, None);
+        assert_eq!(outcome.result.black + outcome.result.white, 64);
+        assert_eq!(outcome.notes.len(), outcome.game.history().len());
+        assert!(outcome.notes.iter().all(|note| note.eval.is_none()));
+    }
+
+    #[test]
+    fn test_notes_record_evals_and_times() {
+        let opening = Game::from_transcript("f5d6").unwrap();
+        let mut hard = CpuPlayer::new(CpuLevel::Hard)
+            .with_config(CpuConfig { limits: SearchLimits::depth(2), book: None, ..CpuConfig::default() });
+        let outcome = play_game(opening, &mut hard, &mut cpu(CpuLevel::Medium), &Clock::unlimited());
+        assert_eq!(outcome.notes[..2], [MoveNote::default(); 2]);
+        for (record, note) in outcome.game.history().iter().zip(&outcome.notes).skip(2) {
+            assert_eq!(note.time.is_some(), record.mv != Move::Pass);
+            assert_eq!(note.eval.is_some(), record.player == Disc::Black && record.mv != Move::Pass);
+        }
+    }
+
+    #[test]
+    fn test_forfeit() {
+        let outcome = play_game(Game::new(), &mut cpu(CpuLevel::Easy), &mut Resigner, &Clock::unlimited());
+        assert_eq!(outcome.forfeit, Some(Disc::White));
+        assert_eq!(outcome.result.winner, Some(Disc::Black));
+        assert_eq!(outcome.game.history().len(), 1);
+    }
+
+    #[test]
+    fn test_run_match_alternates_colours() {
+        let config = MatchConfig { games: 4, opening_plies: 4, seed: Some(9), clock: Clock::unlimited() };
+        let mut colours = Vec::new();
+        let mut openings = Vec::new();
+        let stats = run_match(&mut cpu(CpuLevel::Medium), &mut Resigner, &config, |_, outcome, first_is_black| {
+            colours.push(first_is_black);
+            openings.push(outcome.game.history()[..4].to_vec());
+        });
+        assert_eq!(colours, [true, false, true, false]);
+        assert_eq!(openings[0], openings[1]);
+        assert_eq!(openings[2], openings[3]);
+        assert_eq!(stats, MatchStats { wins: 4, draws: 0, losses: 0, margin_total: 4 * 64 });
+        assert_eq!(stats.elo(), None);
+    }
+
+    #[test]
+    fn test_match_stats() {
+        let mut stats = MatchStats::default();
+        assert_eq!(stats.score(), 0.5);
+        for margin in [10, -4, 0, 20] {
+            stats.record(margin);
+        }
+        assert_eq!((stats.wins, stats.draws, stats.losses), (2, 1, 1));
+        assert_eq!(stats.score(), 0.625);
+        assert_eq!(stats.average_margin(), 6.5);
+
+        let elo = stats.elo().unwrap();
+        assert!((elo.elo - 88.7).abs() < 0.1, "{:?}", elo);
+        assert!(elo.margin > 0.0);
+    }
+}
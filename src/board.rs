@@ -7,6 +7,16 @@ pub enum Disc {
     Empty,
 }
 
+impl Disc {
+    pub fn opponent(self) -> Disc {
+        match self {
+            Disc::Black => Disc::White,
+            Disc::White => Disc::Black,
+            Disc::Empty => Disc::Empty,
+        }
+    }
+}
+
 impl fmt::Display for Disc {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -17,26 +27,192 @@ impl fmt::Display for Disc {
     }
 }
 
-#[derive(Debug, Clone)]
+const NOT_A_FILE: u64 = 0xfefe_fefe_fefe_fefe;
+const NOT_H_FILE: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+
+// Shift amount and wrap-around mask for each of the eight directions.
+// Bit `row * 8 + col` holds square (row, col), so +1 moves right and +8 moves down.
+const DIRECTIONS: [(i32, u64); 8] = [
+    (1, NOT_A_FILE),
+    (-1, NOT_H_FILE),
+    (8, u64::MAX),
+    (-8, u64::MAX),
+    (9, NOT_A_FILE),
+    (7, NOT_H_FILE),
+    (-7, NOT_A_FILE),
+    (-9, NOT_H_FILE),
+];
+
+#[inline]
+fn shift(bits: u64, amount: i32, mask: u64) -> u64 {
+    if amount > 0 {
+        (bits << amount) & mask
+    } else {
+        (bits >> -amount) & mask
+    }
+}
+
+/// Returns the bit for square (row, col).
+#[inline]
+pub fn square_bit(row: usize, col: usize) -> u64 {
+    1u64 << (row * 8 + col)
+}
+
+/// Iterates over the (row, col) squares set in `mask`, in row-major order.
+pub fn squares(mut mask: u64) -> impl Iterator<Item = (usize, usize)> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let index = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some((index / 8, index % 8))
+    })
+}
+
+/// Squares a player with discs `own` may legally play against `opponent`.
+pub fn legal_moves(own: u64, opponent: u64) -> u64 {
+    let empty = !(own | opponent);
+    let mut moves = 0;
+    for &(amount, mask) in &DIRECTIONS {
+        let mut run = shift(own, amount, mask) & opponent;
+        for _ in 0..5 {
+            run |= shift(run, amount, mask) & opponent;
+        }
+        moves |= shift(run, amount, mask) & empty;
+    }
+    moves
+}
+
+/// Discs flipped when the player with discs `own` plays on `square`.
+pub fn flips(square: u64, own: u64, opponent: u64) -> u64 {
+    let mut flipped = 0;
+    for &(amount, mask) in &DIRECTIONS {
+        let mut line = 0;
+        let mut cursor = shift(square, amount, mask);
+        while cursor & opponent != 0 {
+            line |= cursor;
+            cursor = shift(cursor, amount, mask);
+        }
+        if cursor & own != 0 {
+            flipped |= line;
+        }
+    }
+    flipped
+}
+
+/// Squares next to any square in `bits`, in any of the eight directions.
+pub fn neighbours(bits: u64) -> u64 {
+    DIRECTIONS.iter().fold(0, |acc, &(amount, mask)| acc | shift(bits, amount, mask))
+}
+
+/// Discs in `own` that can never be flipped. A disc is stable when, along each of
+/// the four lines through it, the line is full or it touches the edge or a stable
+/// disc of its own colour. This misses a few stable discs but never counts an
+/// unstable one.
+pub fn stable_discs(own: u64, opponent: u64) -> u64 {
+    let empty = !(own | opponent);
+    // Each line as the pair of opposite directions that make it up.
+    let lines = [
+        (DIRECTIONS[0], DIRECTIONS[1]),
+        (DIRECTIONS[2], DIRECTIONS[3]),
+        (DIRECTIONS[4], DIRECTIONS[7]),
+        (DIRECTIONS[5], DIRECTIONS[6]),
+    ];
+    let mut full = [0u64; 4];
+    for (full, &((a, a_mask), (b, b_mask))) in full.iter_mut().zip(&lines) {
+        let mut open = empty;
+        for _ in 0..7 {
+            open |= shift(open, a, a_mask) | shift(open, b, b_mask);
+        }
+        *full = !open;
+    }
+
+    let mut stable = 0;
+    loop {
+        let mut next = own;
+        for (full, &((a, a_mask), (b, b_mask))) in full.iter().zip(&lines) {
+            let anchored_a = shift(stable, a, a_mask) | !shift(u64::MAX, a, a_mask);
+            let anchored_b = shift(stable, b, b_mask) | !shift(u64::MAX, b, b_mask);
+            next &= full | anchored_a | anchored_b;
+        }
+        if next == stable {
+            return stable;
+        }
+        stable = next;
+    }
+}
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+// Zobrist keys: one per (colour, square), plus one for white to move.
+const ZOBRIST: ([[u64; 64]; 2], u64) = {
+    let mut keys = [[0; 64]; 2];
+    let mut state = 0x0123_4567_89ab_cdef;
+    let mut colour = 0;
+    while colour < 2 {
+        let mut square = 0;
+        while square < 64 {
+            let (next, key) = splitmix64(state);
+            keys[colour][square] = key;
+            state = next;
+            square += 1;
+        }
+        colour += 1;
+    }
+    let (_, side) = splitmix64(state);
+    (keys, side)
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Board {
-    grid: [[Disc; 8]; 8],
+    black: u64,
+    white: u64,
 }
 
 impl Board {
     pub fn new() -> Self {
-        let mut grid = [[Disc::Empty; 8]; 8];
-        grid[3][3] = Disc::White;
-        grid[3][4] = Disc::Black;
-        grid[4][3] = Disc::Black;
-        grid[4][4] = Disc::White;
-        Board { grid }
+        let mut board = Board::empty();
+        board.put_disc(3, 3, Disc::White);
+        board.put_disc(3, 4, Disc::Black);
+        board.put_disc(4, 3, Disc::Black);
+        board.put_disc(4, 4, Disc::White);
+        board
     }
 
-    
+    pub fn empty() -> Self {
+        Board { black: 0, white: 0 }
+    }
+
+    /// Builds a board from raw bitboards. Squares set in both masks are treated as black.
+    pub fn from_bitboards(black: u64, white: u64) -> Self {
+        Board { black, white: white & !black }
+    }
+
+    pub fn bitboard(&self, disc: Disc) -> u64 {
+        match disc {
+            Disc::Black => self.black,
+            Disc::White => self.white,
+            Disc::Empty => !(self.black | self.white),
+        }
+    }
 
     pub fn get_disc(&self, row: usize, col: usize) -> Option<Disc> {
         if row < 8 && col < 8 {
-            Some(self.grid[row][col])
+            let bit = square_bit(row, col);
+            if self.black & bit != 0 {
+                Some(Disc::Black)
+            } else if self.white & bit != 0 {
+                Some(Disc::White)
+            } else {
+                Some(Disc::Empty)
+            }
         } else {
             None
         }
@@ -44,9 +220,79 @@ impl Board {
 
     pub fn put_disc(&mut self, row: usize, col: usize, disc: Disc) {
         if row < 8 && col < 8 {
-            self.grid[row][col] = disc;
+            let bit = square_bit(row, col);
+            self.black &= !bit;
+            self.white &= !bit;
+            match disc {
+                Disc::Black => self.black |= bit,
+                Disc::White => self.white |= bit,
+                Disc::Empty => (),
+            }
         }
     }
+
+    /// Zobrist hash of the position with `side_to_move` to play.
+    pub fn hash(&self, side_to_move: Disc) -> u64 {
+        let mut hash = if side_to_move == Disc::White { ZOBRIST.1 } else { 0 };
+        for (colour, bits) in [self.black, self.white].into_iter().enumerate() {
+            let mut bits = bits;
+            while bits != 0 {
+                hash ^= ZOBRIST.0[colour][bits.trailing_zeros() as usize];
+                bits &= bits - 1;
+            }
+        }
+        hash
+    }
+
+    pub fn count(&self, disc: Disc) -> u32 {
+        self.bitboard(disc).count_ones()
+    }
+
+    pub fn legal_moves(&self, player: Disc) -> u64 {
+        match player {
+            Disc::Empty => 0,
+            _ => legal_moves(self.bitboard(player), self.bitboard(player.opponent())),
+        }
+    }
+
+    pub fn flips(&self, row: usize, col: usize, player: Disc) -> u64 {
+        if row >= 8 || col >= 8 || player == Disc::Empty {
+            return 0;
+        }
+        let bit = square_bit(row, col);
+        if (self.black | self.white) & bit != 0 {
+            return 0;
+        }
+        flips(bit, self.bitboard(player), self.bitboard(player.opponent()))
+    }
+
+    /// Places `player` on (row, col) and flips the captured discs, returning the
+    /// flipped mask. Nothing changes and 0 is returned if the move is illegal.
+    pub fn apply_move(&mut self, row: usize, col: usize, player: Disc) -> u64 {
+        let flipped = self.flips(row, col, player);
+        if flipped == 0 {
+            return 0;
+        }
+        let bit = square_bit(row, col);
+        match player {
+            Disc::Black => {
+                self.black |= bit | flipped;
+                self.white &= !flipped;
+            }
+            Disc::White => {
+                self.white |= bit | flipped;
+                self.black &= !flipped;
+            }
+            Disc::Empty => unreachable!(),
+        }
+        flipped
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -81,6 +327,114 @@ mod tests {
         assert_eq!(board.get_disc(8, 8), None);
     }
 
+    #[test]
+    fn test_put_disc_overwrites_and_clears() {
+        let mut board = Board::new();
+        board.put_disc(3, 3, Disc::Black);
+        assert_eq!(board.get_disc(3, 3), Some(Disc::Black));
+        assert_eq!(board.count(Disc::White), 1);
+
+        board.put_disc(3, 3, Disc::Empty);
+        assert_eq!(board.get_disc(3, 3), Some(Disc::Empty));
+        assert_eq!(board.count(Disc::Black), 2);
+    }
+
+    #[test]
+    fn test_legal_moves_initial_position() {
+        let board = Board::new();
+        let moves: Vec<_> = squares(board.legal_moves(Disc::Black)).collect();
+        assert_eq!(moves, vec![(2, 3), (3, 2), (4, 5), (5, 4)]);
+        let moves: Vec<_> = squares(board.legal_moves(Disc::White)).collect();
+        assert_eq!(moves, vec![(2, 4), (3, 5), (4, 2), (5, 3)]);
+    }
+
+    #[test]
+    fn test_legal_moves_do_not_wrap_around_edges() {
+        // Black on h1 and white on a2 are adjacent in bit order but not on the board.
+        let mut board = Board::empty();
+        board.put_disc(0, 7, Disc::Black);
+        board.put_disc(1, 0, Disc::White);
+        assert_eq!(board.legal_moves(Disc::Black), 0);
+    }
+
+    #[test]
+    fn test_apply_move_flips_multiple_lines() {
+        let mut board = Board::empty();
+        board.put_disc(1, 1, Disc::White);
+        board.put_disc(1, 2, Disc::White);
+        board.put_disc(1, 3, Disc::Black);
+        board.put_disc(2, 1, Disc::White);
+        board.put_disc(3, 2, Disc::Black);
+        board.put_disc(0, 1, Disc::White);
+
+        let flipped = board.apply_move(1, 0, Disc::Black);
+        assert_eq!(flipped, square_bit(1, 1) | square_bit(1, 2) | square_bit(2, 1));
+        assert_eq!(board.get_disc(1, 0), Some(Disc::Black));
+        assert_eq!(board.get_disc(1, 2), Some(Disc::Black));
+        assert_eq!(board.get_disc(2, 1), Some(Disc::Black));
+        assert_eq!(board.get_disc(0, 1), Some(Disc::White));
+        assert_eq!(board.count(Disc::Black), 6);
+    }
+
+    #[test]
+    fn test_apply_move_illegal_leaves_board_unchanged() {
+        let mut board = Board::new();
+        assert_eq!(board.apply_move(0, 0, Disc::Black), 0);
+        assert_eq!(board.apply_move(3, 3, Disc::Black), 0);
+        assert_eq!(board, Board::new());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        assert_eq!(neighbours(square_bit(0, 0)), square_bit(0, 1) | square_bit(1, 0) | square_bit(1, 1));
+        assert_eq!(neighbours(square_bit(3, 7)).count_ones(), 5);
+    }
+
+    #[test]
+    fn test_stable_discs() {
+        let mut board = Board::empty();
+        for c in 0..3 {
+            board.put_disc(0, c, Disc::Black);
+        }
+        board.put_disc(1, 0, Disc::Black);
+        board.put_disc(1, 1, Disc::Black);
+        board.put_disc(0, 3, Disc::White);
+        board.put_disc(4, 4, Disc::Black);
+        let stable = stable_discs(board.bitboard(Disc::Black), board.bitboard(Disc::White));
+        // The corner group is anchored; the lone centre disc is not.
+        let corner = square_bit(0, 0) | square_bit(0, 1) | square_bit(0, 2) | square_bit(1, 0) | square_bit(1, 1);
+        assert_eq!(stable, corner);
+        assert_eq!(stable_discs(Board::new().bitboard(Disc::Black), Board::new().bitboard(Disc::White)), 0);
+    }
+
+    #[test]
+    fn test_hash_depends_on_position_and_side() {
+        let board = Board::new();
+        assert_ne!(board.hash(Disc::Black), board.hash(Disc::White));
+        assert_ne!(board.hash(Disc::Black), Board::empty().hash(Disc::Black));
+
+        let mut moved = board;
+        moved.apply_move(2, 3, Disc::Black);
+        assert_ne!(moved.hash(Disc::White), board.hash(Disc::White));
+    }
+
+    #[test]
+    fn test_hash_matches_for_transpositions() {
+        // d3 c3 c4 and c4 c3 d3 reach the same position.
+        let mut a = Board::new();
+        a.apply_move(2, 3, Disc::Black);
+        a.apply_move(2, 2, Disc::White);
+        a.apply_move(3, 2, Disc::Black);
+
+        let mut b = Board::new();
+        b.apply_move(3, 2, Disc::Black);
+        b.apply_move(2, 2, Disc::White);
+        b.apply_move(2, 3, Disc::Black);
+
+        assert_eq!(a, b);
+        assert_eq!(a.hash(Disc::White), b.hash(Disc::White));
+    }
+
     #[test]
     fn test_disc_display() {
         assert_eq!(format!("{}", Disc::Black), "●");
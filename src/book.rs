@@ -0,0 +1,309 @@
+use crate::board::{square_bit, squares, Board, Disc};
+use crate::game::{Game, Move};
+use crate::notation::{tokenize, TranscriptError};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+const BUILTIN_BOOK: &str = include_str!("../books/openings.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    pub mv: (usize, usize),
+    pub weight: u32,
+}
+
+/// Opening book built from game transcripts.
+///
+/// Each line of a book file is a transcript such as `f5d6c3d3c4`, optionally
+/// followed by a weight (default 1); `#` starts a comment. Every position along a
+/// line records the move played from it. Positions are stored in a canonical
+/// orientation, so a line also covers its seven rotations and reflections.
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    lines: Vec<(String, u32)>,
+    // Canonical (side to move, opponent) bitboards -> canonical square index -> weight.
+    positions: HashMap<(u64, u64), HashMap<u8, u32>>,
+}
+
+impl Book {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The book shipped with the program (`books/openings.txt`).
+    pub fn builtin() -> Arc<Book> {
+        static BUILTIN: OnceLock<Arc<Book>> = OnceLock::new();
+        BUILTIN
+            .get_or_init(|| Arc::new(Book::parse(BUILTIN_BOOK).expect("built-in opening book is valid")))
+            .clone()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Book> {
+        Book::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Book> {
+        let mut book = Book::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let transcript = fields.next().unwrap();
+            let weight = match fields.next() {
+                Some(weight) => weight.parse().map_err(|_| {
+                    invalid_data(format!("line {}: invalid weight '{}'", number + 1, weight))
+                })?,
+                None => 1,
+            };
+            book.add_line(transcript, weight)
+                .map_err(|message| invalid_data(format!("line {}: {}", number + 1, message)))?;
+        }
+        Ok(book)
+    }
+
+    /// Adds a transcript to the book. Forced passes are inserted automatically.
+    pub fn add_line(&mut self, transcript: &str, weight: u32) -> Result<(), TranscriptError> {
+        let game = Game::from_transcript(transcript)?;
+
+        let mut board = Board::new();
+        for record in game.history() {
+            if let Move::Place(row, col) = record.mv {
+                let (key, transform) = canonical(&board, record.player);
+                let (r, c) = transform_square(transform, row, col);
+                *self.positions.entry(key).or_default().entry((r * 8 + c) as u8).or_default() += weight;
+                board.apply_move(row, col, record.player);
+            }
+        }
+        self.lines.push((game.transcript(), weight));
+        Ok(())
+    }
+
+    /// Adds the first `max_plies` moves of each transcript, e.g. from a file of game records.
+    pub fn extend_from_games<'a>(
+        &mut self,
+        transcripts: impl IntoIterator<Item = &'a str>,
+        max_plies: usize,
+    ) -> Result<usize, TranscriptError> {
+        let mut added = 0;
+        for transcript in transcripts {
+            let prefix: String = tokenize(transcript).into_iter().take(max_plies).collect();
+            if !prefix.is_empty() {
+                self.add_line(&prefix, 1)?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (transcript, weight) in &self.lines {
+            if *weight == 1 {
+                writeln!(writer, "{}", transcript)?;
+            } else {
+                writeln!(writer, "{} {}", transcript, weight)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        self.write(&mut file)
+    }
+
+    /// Number of distinct positions in the book.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Book moves for `player` to move on `board`, heaviest first.
+    pub fn moves(&self, board: &Board, player: Disc) -> Vec<BookMove> {
+        let (key, transform) = canonical(board, player);
+        let Some(entries) = self.positions.get(&key) else {
+            return Vec::new();
+        };
+        let inverse = inverse_transform(transform);
+        let mut moves: Vec<BookMove> = entries
+            .iter()
+            .map(|(&square, &weight)| BookMove {
+                mv: transform_square(inverse, square as usize / 8, square as usize % 8),
+                weight,
+            })
+            .collect();
+        moves.sort_by(|a, b| b.weight.cmp(&a.weight).then(a.mv.cmp(&b.mv)));
+        moves
+    }
+
+    /// Picks a book move at random, weighted by how often it was played.
+    pub fn choose<R: Rng + ?Sized>(&self, board: &Board, player: Disc, rng: &mut R) -> Option<(usize, usize)> {
+        let moves = self.moves(board, player);
+        let total: u32 = moves.iter().map(|m| m.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.random_range(0..total);
+        for book_move in moves {
+            if pick < book_move.weight {
+                return Some(book_move.mv);
+            }
+            pick -= book_move.weight;
+        }
+        unreachable!()
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The eight symmetries of the board, as maps of (row, col).
+pub(crate) fn transform_square(transform: usize, row: usize, col: usize) -> (usize, usize) {
+    match transform {
+        0 => (row, col),
+        1 => (col, row),
+        2 => (7 - row, 7 - col),
+        3 => (7 - col, 7 - row),
+        4 => (row, 7 - col),
+        5 => (7 - row, col),
+        6 => (col, 7 - row),
+        7 => (7 - col, row),
+        _ => unreachable!(),
+    }
+}
+
+fn inverse_transform(transform: usize) -> usize {
+    // Only the two quarter turns are not their own inverse.
+    match transform {
+        6 => 7,
+        7 => 6,
+        t => t,
+    }
+}
+
+fn transform_bits(transform: usize, bits: u64) -> u64 {
+    squares(bits).fold(0, |acc, (r, c)| {
+        let (r, c) = transform_square(transform, r, c);
+        acc | square_bit(r, c)
+    })
+}
+
+// Returns the canonical key of the position and the transform that produces it.
+fn canonical(board: &Board, player: Disc) -> ((u64, u64), usize) {
+    let own = board.bitboard(player);
+    let opponent = board.bitboard(player.opponent());
+    (0..8)
+        .map(|t| ((transform_bits(t, own), transform_bits(t, opponent)), t))
+        .min()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_transforms_round_trip() {
+        for t in 0..8 {
+            for r in 0..8 {
+                for c in 0..8 {
+                    let (tr, tc) = transform_square(t, r, c);
+                    assert_eq!(transform_square(inverse_transform(t), tr, tc), (r, c));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetric_openings_share_entries() {
+        let book = Book::parse("f5d6\n").unwrap();
+        let start = Board::new();
+
+        // All four first moves are symmetric, so each one is answered by the book.
+        let first: Vec<_> = book.moves(&start, Disc::Black).iter().map(|m| m.mv).collect();
+        assert_eq!(first.len(), 1);
+        for (r, c) in squares(start.legal_moves(Disc::Black)) {
+            let mut board = start;
+            board.apply_move(r, c, Disc::Black);
+            let replies = book.moves(&board, Disc::White);
+            assert_eq!(replies.len(), 1);
+            assert!(board.legal_moves(Disc::White) & square_bit(replies[0].mv.0, replies[0].mv.1) != 0);
+        }
+
+        // After d3 (the mirror image of f5), the answer is c5, the mirror image of d6.
+        let mut board = start;
+        board.apply_move(2, 3, Disc::Black);
+        assert_eq!(book.moves(&board, Disc::White)[0].mv, (4, 2));
+    }
+
+    #[test]
+    fn test_weights_accumulate() {
+        let book = Book::parse("f5d6c3 # tiger\nf5f6 3\nf5d6c5\n").unwrap();
+        let mut board = Board::new();
+        board.apply_move(4, 5, Disc::Black);
+        let replies = book.moves(&board, Disc::White);
+        assert_eq!(replies, vec![
+            BookMove { mv: (5, 5), weight: 3 },
+            BookMove { mv: (5, 3), weight: 2 },
+        ]);
+        assert_eq!(book.moves(&Board::new(), Disc::Black)[0].weight, 5);
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_line() {
+        let error = Book::parse("f5d6\nf5a1\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: illegal move a1 at ply 2");
+        let error = Book::parse("f5 x\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: invalid weight 'x'");
+    }
+
+    #[test]
+    fn test_choose_returns_book_move() {
+        let book = Book::parse("f5d6\nf5f6\nf5f4\n").unwrap();
+        let mut board = Board::new();
+        board.apply_move(4, 5, Disc::Black);
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let mv = book.choose(&board, Disc::White, &mut rng).unwrap();
+            assert!([(5, 3), (5, 5), (3, 5)].contains(&mv));
+        }
+        assert_eq!(book.choose(&Board::empty(), Disc::Black, &mut rng), None);
+    }
+
+    #[test]
+    fn test_extend_from_games_and_write() {
+        let mut book = Book::new();
+        let added = book.extend_from_games(["f5d6c3d3c4f4", "f5f6e6f4"], 3).unwrap();
+        assert_eq!(added, 2);
+        let mut out = Vec::new();
+        book.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "f5d6c3\nf5f6e6\n");
+    }
+
+    #[test]
+    fn test_builtin_book_loads() {
+        let book = Book::builtin();
+        assert!(!book.is_empty());
+        assert!(!book.moves(&Board::new(), Disc::Black).is_empty());
+    }
+
+    #[test]
+    fn test_builtin_lines_leave_game_in_play() {
+        for line in BUILTIN_BOOK.lines().map(|line| line.split('#').next().unwrap().trim()) {
+            let Some(transcript) = line.split_whitespace().next() else { continue };
+            let game = Game::from_transcript(transcript).unwrap_or_else(|e| panic!("{}: {}", transcript, e));
+            assert!(!game.is_game_over(), "{} ends the game", transcript);
+        }
+    }
+}
@@ -0,0 +1,1319 @@
+use gemini_claude_othello::analysis::{self, GameAnalysis, Judgement, DEFAULT_ANALYSIS_DEPTH};
+use gemini_claude_othello::arena::{self, GameOutcome, MatchConfig};
+use gemini_claude_othello::book::Book;
+use gemini_claude_othello::cpu::{CpuConfig, SearchLimits};
+use gemini_claude_othello::endgame::{self, SolveMode};
+use gemini_claude_othello::eval;
+use gemini_claude_othello::ggf::{self, GgfGame};
+use gemini_claude_othello::pattern::{self, PatternWeights, Sample, TrainOptions};
+use gemini_claude_othello::gtp::{self, GtpClient};
+use gemini_claude_othello::{nboard, perft};
+use gemini_claude_othello::tournament::{self, Entrant, PlayerFactory, Schedule, TournamentConfig};
+use gemini_claude_othello::notation::format_square;
+use gemini_claude_othello::save::{SavedGame, SAVE_EXTENSION};
+use gemini_claude_othello::wthor::{self, GameDatabase, NameKind, WthorGame};
+use gemini_claude_othello::{Board, Clock, CpuLevel, CpuPlayer, Disc, Game, Player, PlayerType};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage:
+  gemini-claude-othello [OPTIONS]            Play in the terminal UI
+  gemini-claude-othello book show [--book FILE] [TRANSCRIPT]
+  gemini-claude-othello book build [--plies N] OUTPUT GAMES_FILE...
+  gemini-claude-othello solve [--wld] [--force] (POSITION | --file FILE)
+  gemini-claude-othello wthor show [--players FILE.JOU] [--tournaments FILE.TRN] FILE.wtb...
+                                             List the games of WTHOR databases
+  gemini-claude-othello wthor write [--year YEAR] [--players FILE.JOU] [--tournaments FILE.TRN]
+                                   [--tournament NAME] OUTPUT.wtb GAMES_FILE...
+                                             Convert transcripts or GGF games to a WTHOR database
+  gemini-claude-othello train [--epochs N] [--rate R] [--seed N] [--init FILE] OUTPUT DATA_FILE...
+                                             Fit pattern evaluation weights to a game database
+  gemini-claude-othello analyze [--depth N] [CPU OPTIONS] (TRANSCRIPT | --file FILE [--game N])
+                                             Score every move of a game and flag mistakes
+  gemini-claude-othello perft [--divide] [POSITION | --moves TRANSCRIPT] DEPTH
+                                             Count move-generation nodes to DEPTH
+  gemini-claude-othello nboard [CPU OPTIONS]  Speak the NBoard engine protocol
+  gemini-claude-othello gtp [--level LEVEL] [CPU OPTIONS]
+                                             Speak a GTP-style protocol (default level: hard)
+  gemini-claude-othello match [--games N] [--openings PLIES] [--record FILE] [CPU OPTIONS] ENGINE ENGINE
+                                             Play a headless match and report statistics
+  gemini-claude-othello tournament [--gauntlet] [--games N] [--openings PLIES] [--jobs N]
+                                   [--record FILE] [CPU OPTIONS] ENGINE...
+                                             Play a round-robin or gauntlet tournament
+
+Options (given any of these, the menus are skipped):
+  --black PLAYER        human, easy, medium or hard (default: human)
+  --white PLAYER        human, easy, medium or hard (default: human)
+  --position POSITION   Start from POSITION instead of the initial position
+  --moves TRANSCRIPT    Play these moves first, e.g. f5d6c3
+  --delay SECONDS       Pause between moves when two CPUs play (default: 1)
+  --clock MIN[+INC]     Give each side MIN minutes for the game, plus INC seconds
+                        per move; running out of time loses (default: untimed)
+  --replay FILE         Step through a game record instead of playing: a save file,
+                        a .ggf or .wtb database, or transcripts, one per line
+  --game N              Which game of the --replay file to show (default: 1)
+
+CPU options (also accepted by the engine protocols):
+  --time SECONDS        Hard CPU thinking time per move (default: 2)
+  --endgame N           Solve exactly with N or fewer empty squares left, within
+                        the thinking time (default: 12)
+  --seed N              Make CPU random choices reproducible
+  --book FILE           Use FILE as the opening book
+  --no-book             Do not use an opening book
+  --eval FILE           Load Hard CPU evaluation weights (feature or pattern) from FILE
+  --hash MB             Hard CPU transposition table size in megabytes (default: 16)
+
+ENGINE is easy, medium or hard, optionally with its own thinking time in
+seconds, e.g. hard:0.5. --openings plays that many random moves before each
+pair of games; --seed also makes the openings reproducible.
+
+Tournaments also accept external engines as \"gtp:COMMAND ARGS\". Every pair
+plays --games games (default: 2); --gauntlet pairs the first ENGINE with each
+of the others only. --jobs sets how many games run at once (default: one per
+core).
+
+--record writes every game of a match or tournament to FILE: as GGF records
+with the CPU evaluations and thinking times if FILE ends in .ggf, and as
+transcripts otherwise.
+
+Training data files hold one finished game transcript per line, or scored
+positions as \"POSITION; SCORE\" with the final disc margin for the side to move.
+book build and train also read WTHOR databases (files ending in .wtb) and GGF
+game records (files ending in .ggf).
+
+analyze searches every position of the game to --depth plies (default: 8),
+or for --time SECONDS if given, and reports each move's loss in discs against
+the best move: 2 or more is an inaccuracy (?!), 4 a mistake (?) and 8 a
+blunder (??). FILE is read as by --replay.
+
+POSITION is 64 squares of X/O/- (a1 to h8) followed by the side to move,
+e.g. \"---------------------------OX------XO--------------------------- X\".
+solve refuses positions with more than 24 empty squares unless --force is given.";
+
+/// Game setup given with command-line options.
+pub struct PlayOptions {
+    pub game: Game,
+    pub black: PlayerType,
+    pub white: PlayerType,
+    pub config: CpuConfig,
+    /// The CPU options behind `config`, for save files, with file paths made
+    /// absolute so a saved game can be resumed from anywhere.
+    pub cpu_options: Vec<String>,
+    /// Pause between moves when two CPUs play each other.
+    pub delay: Duration,
+    /// The game clock; a side that runs out of time loses.
+    pub clock: Clock,
+    /// A game record to step through instead of playing.
+    pub replay: Option<Game>,
+}
+
+pub const DEFAULT_MOVE_DELAY: Duration = Duration::from_secs(1);
+
+/// True if the arguments are play options rather than a subcommand.
+pub fn is_play_options(args: &[String]) -> bool {
+    args.first().is_some_and(|arg| arg.starts_with("--") && arg != "--help")
+}
+
+pub fn parse_play_options(args: &[String]) -> Result<PlayOptions, String> {
+    let mut options = PlayOptions {
+        game: Game::new(),
+        black: PlayerType::Human,
+        white: PlayerType::Human,
+        config: CpuConfig::default(),
+        cpu_options: Vec::new(),
+        delay: DEFAULT_MOVE_DELAY,
+        clock: Clock::unlimited(),
+        replay: None,
+    };
+    let mut moves = None;
+    let mut replay = None;
+    let mut number = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--black" => options.black = parse_player(value()?)?,
+            "--white" => options.white = parse_player(value()?)?,
+            "--position" => {
+                let position = value()?;
+                options.game = Game::from_position_str(position).map_err(|e| format!("--position: {}", e))?;
+            }
+            "--moves" => moves = Some(value()?),
+            "--delay" => {
+                let text = value()?;
+                let seconds: f64 = text.parse().map_err(|_| format!("invalid delay '{}'", text))?;
+                if !seconds.is_finite() || seconds < 0.0 {
+                    return Err(format!("invalid delay '{}'", text));
+                }
+                options.delay = Duration::from_secs_f64(seconds);
+            }
+            "--clock" => options.clock = parse_clock(value()?)?,
+            "--replay" => replay = Some(value()?),
+            "--game" => {
+                let text = value()?;
+                let parsed = text.parse().ok().filter(|number| *number > 0);
+                number = Some(parsed.ok_or_else(|| format!("invalid game '{}'", text))?);
+            }
+            other => {
+                let rest = args.as_slice();
+                if !apply_cpu_option(&mut options.config, other, &mut args)? {
+                    return Err(format!("unknown option '{}'\n\n{}", other, USAGE));
+                }
+                let values = &rest[..rest.len() - args.as_slice().len()];
+                options.cpu_options.push(other.to_string());
+                options.cpu_options.extend(values.iter().map(|value| match other {
+                    "--book" | "--eval" => {
+                        std::path::absolute(value).map_or(value.clone(), |path| path.display().to_string())
+                    }
+                    _ => value.clone(),
+                }));
+            }
+        }
+    }
+    if let Some(moves) = moves {
+        options.game.play_transcript(moves).map_err(|e| format!("--moves: {}", e))?;
+    }
+    match (replay, number) {
+        (Some(path), number) => options.replay = Some(load_record(path, number.unwrap_or(1))?),
+        (None, Some(_)) => return Err("--game needs --replay".to_string()),
+        (None, None) => {}
+    }
+    Ok(options)
+}
+
+// MIN[+INC]: minutes per side for the whole game, plus seconds added after each move.
+fn parse_clock(text: &str) -> Result<Clock, String> {
+    let (minutes, increment) = text.split_once('+').unwrap_or((text, "0"));
+    let seconds = |value: &str, scale: f64| {
+        value.parse::<f64>()
+            .ok()
+            .map(|value| value * scale)
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+            .map(Duration::from_secs_f64)
+            .ok_or_else(|| format!("invalid clock '{}'", text))
+    };
+    let time = seconds(minutes, 60.0)?;
+    if time.is_zero() {
+        return Err(format!("invalid clock '{}'", text));
+    }
+    Ok(Clock::total(time, seconds(increment, 1.0)?))
+}
+
+// Game `number` (counted from 1) of a save file, a WTHOR or GGF database, or a file
+// of transcripts.
+fn load_record(path: &str, number: usize) -> Result<Game, String> {
+    let games = if has_extension(path, SAVE_EXTENSION) {
+        vec![SavedGame::load(path).map_err(|e| format!("{}: {}", path, e))?.game]
+    } else if let Some(games) = load_game_database(path)? {
+        games
+    } else {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        text.lines()
+            .enumerate()
+            .map(|(number, line)| (number, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, line)| Game::from_transcript(line).map_err(|e| format!("{}:{}: {}", path, number + 1, e)))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let count = games.len();
+    games.into_iter().nth(number - 1).ok_or_else(|| format!("{}: there is no game {} ({} games)", path, number, count))
+}
+
+// Handles the CPU options shared by play mode and the engine protocols, taking a
+// value from `args` where needed. Returns `Ok(false)` for any other argument.
+fn apply_cpu_option(config: &mut CpuConfig, arg: &str, args: &mut std::slice::Iter<String>) -> Result<bool, String> {
+    let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+    match arg {
+        "--time" => {
+            let text = value()?;
+            let seconds: f64 = text.parse().map_err(|_| format!("invalid time '{}'", text))?;
+            if !seconds.is_finite() || seconds <= 0.0 {
+                return Err(format!("invalid time '{}'", text));
+            }
+            config.limits.time = Some(Duration::from_secs_f64(seconds));
+        }
+        "--endgame" => {
+            let text = value()?;
+            let parsed = text.parse().ok().filter(|empties| *empties <= 60);
+            config.limits.endgame_empties = parsed.ok_or_else(|| format!("invalid endgame empties '{}'", text))?;
+        }
+        "--seed" => {
+            let text = value()?;
+            config.seed = Some(text.parse().map_err(|_| format!("invalid seed '{}'", text))?);
+        }
+        "--book" => {
+            let path = value()?;
+            config.book = Some(Arc::new(Book::load(path).map_err(|e| format!("{}: {}", path, e))?));
+        }
+        "--no-book" => config.book = None,
+        "--hash" => {
+            let text = value()?;
+            config.tt_size_mb = text.parse().map_err(|_| format!("invalid hash size '{}'", text))?;
+        }
+        "--eval" => {
+            let path = value()?;
+            config.evaluator = eval::load(path).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Reads CPU options alone, as the engine protocols take them and save files
+/// record them.
+pub fn parse_cpu_options(args: &[String]) -> Result<CpuConfig, String> {
+    let mut config = CpuConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !apply_cpu_option(&mut config, arg, &mut args)? {
+            return Err(format!("unknown option '{}'\n\n{}", arg, USAGE));
+        }
+    }
+    Ok(config)
+}
+
+fn parse_player(text: &str) -> Result<PlayerType, String> {
+    match text.to_ascii_lowercase().as_str() {
+        "human" => Ok(PlayerType::Human),
+        "easy" => Ok(PlayerType::Cpu(CpuLevel::Easy)),
+        "medium" => Ok(PlayerType::Cpu(CpuLevel::Medium)),
+        "hard" => Ok(PlayerType::Cpu(CpuLevel::Hard)),
+        _ => Err(format!("unknown player '{}' (expected human, easy, medium or hard)", text)),
+    }
+}
+
+/// Runs a subcommand given on the command line.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("book") => run_book(&args[1..]),
+        Some("solve") => run_solve(&args[1..]),
+        Some("perft") => run_perft(&args[1..]),
+        Some("analyze") => run_analyze(&args[1..]),
+        Some("train") => run_train(&args[1..]),
+        Some("wthor") => run_wthor(&args[1..]),
+        Some("nboard") => {
+            let config = parse_cpu_options(&args[1..])?;
+            nboard::run(io::stdin().lock(), io::stdout().lock(), config).map_err(|e| e.to_string())
+        }
+        Some("gtp") => run_gtp(&args[1..]),
+        Some("match") => run_match(&args[1..]),
+        Some("tournament") => run_tournament(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(format!("unknown command '{}'\n\n{}", other, USAGE)),
+        None => Err(USAGE.to_string()),
+    }
+}
+
+fn run_gtp(args: &[String]) -> Result<(), String> {
+    let mut level = CpuLevel::Hard;
+    let mut config = CpuConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--level" {
+            level = match parse_player(args.next().ok_or("--level needs a value")?)? {
+                PlayerType::Cpu(level) => level,
+                PlayerType::Human => return Err("--level must be easy, medium or hard".to_string()),
+            };
+        } else if !apply_cpu_option(&mut config, arg, &mut args)? {
+            return Err(format!("unknown option '{}'\n\n{}", arg, USAGE));
+        }
+    }
+    let player = CpuPlayer::new(level).with_config(config);
+    gtp::run(io::stdin().lock(), io::stdout().lock(), Box::new(player)).map_err(|e| e.to_string())
+}
+
+// An engine given as LEVEL or LEVEL:SECONDS, e.g. "hard:0.5".
+fn parse_engine(spec: &str, config: &CpuConfig) -> Result<CpuPlayer, String> {
+    let (level, time) = match spec.split_once(':') {
+        Some((level, time)) => (level, Some(time)),
+        None => (spec, None),
+    };
+    let level = match parse_player(level)? {
+        PlayerType::Cpu(level) => level,
+        PlayerType::Human => return Err(format!("engine '{}' must be easy, medium or hard", spec)),
+    };
+    let mut config = config.clone();
+    if let Some(time) = time {
+        match time.parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds > 0.0 => {
+                config.limits.time = Some(Duration::from_secs_f64(seconds));
+            }
+            _ => return Err(format!("invalid time in engine '{}'", spec)),
+        }
+    }
+    Ok(CpuPlayer::new(level).with_config(config))
+}
+
+fn run_match(args: &[String]) -> Result<(), String> {
+    let mut match_config = MatchConfig::default();
+    let mut config = CpuConfig::default();
+    let mut record_path = None;
+    let mut engines = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => {
+                let text = args.next().ok_or("--games needs a value")?;
+                match_config.games = text.parse().map_err(|_| format!("invalid game count '{}'", text))?;
+            }
+            "--openings" => {
+                let text = args.next().ok_or("--openings needs a value")?;
+                match_config.opening_plies = text.parse().map_err(|_| format!("invalid ply count '{}'", text))?;
+            }
+            "--record" => record_path = Some(args.next().ok_or("--record needs a file")?),
+            _ if apply_cpu_option(&mut config, arg, &mut args)? => {}
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => engines.push(arg.clone()),
+        }
+    }
+    let [first_spec, second_spec] = engines.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+    match_config.seed = config.seed;
+    let mut first = parse_engine(first_spec, &config)?;
+    let mut second = parse_engine(second_spec, &config)?;
+
+    println!("{} vs {}, {} games", first_spec, second_spec, match_config.games);
+    let mut played = Vec::new();
+    let stats = arena::run_match(&mut first, &mut second, &match_config, |index, outcome, first_is_black| {
+        let (black, white) = if first_is_black { (first_spec, second_spec) } else { (second_spec, first_spec) };
+        played.push((black.as_str(), white.as_str(), outcome.clone()));
+        let forfeit = match outcome.forfeit {
+            Some(Disc::Black) => " (Black forfeits)",
+            Some(_) => " (White forfeits)",
+            None => "",
+        };
+        println!(
+            "  game {}: {} {}-{} {}{}",
+            index + 1,
+            black,
+            outcome.result.black,
+            outcome.result.white,
+            white,
+            forfeit
+        );
+    });
+    if let Some(path) = record_path {
+        write_record(path, &played, &match_config.clock)?;
+    }
+
+    println!(
+        "{}: +{} ={} -{} (score {:.1}%)",
+        first_spec,
+        stats.wins,
+        stats.draws,
+        stats.losses,
+        stats.score() * 100.0
+    );
+    println!("Average disc differential: {:+.2}", stats.average_margin());
+    match stats.elo() {
+        Some(elo) if elo.margin.is_finite() => {
+            println!("Elo difference: {:+.0} ± {:.0} (95% confidence)", elo.elo, elo.margin)
+        }
+        Some(elo) => println!("Elo difference: {:+.0} (too few games for a confidence interval)", elo.elo),
+        None => println!("Elo difference: unbounded (one side won every game)"),
+    }
+    Ok(())
+}
+
+// Writes finished games, given with the names of Black and White, as GGF records
+// if `path` ends in `.ggf` and as commented transcripts otherwise.
+fn write_record(path: &str, games: &[(&str, &str, GameOutcome)], clock: &Clock) -> Result<(), String> {
+    let mut text = String::new();
+    for (index, (black, white, outcome)) in games.iter().enumerate() {
+        if has_extension(path, "ggf") {
+            let mut record = GgfGame::from_outcome(outcome, black, white);
+            record.place = Some(nboard::ENGINE_NAME.to_string());
+            record.time_control = ggf::time_control(clock);
+            text.push_str(&format!("{}\n", record));
+        } else {
+            text.push_str(&format!(
+                "# game {}: {} (Black) {}-{} {} (White)\n{}\n",
+                index + 1,
+                black,
+                outcome.result.black,
+                outcome.result.white,
+                white,
+                outcome.game.transcript()
+            ));
+        }
+    }
+    fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+}
+
+// A tournament entrant: a built-in ENGINE, or "gtp:COMMAND ARGS" for an external
+// program, which is started afresh for every game.
+fn parse_entrant(spec: &str, config: &CpuConfig) -> Result<Entrant, String> {
+    let make: PlayerFactory = match spec.strip_prefix("gtp:") {
+        Some(command) => {
+            let words: Vec<String> = command.split_whitespace().map(String::from).collect();
+            if words.is_empty() {
+                return Err(format!("engine '{}' has no command", spec));
+            }
+            let name = spec.to_string();
+            Arc::new(move || {
+                let args: Vec<&str> = words[1..].iter().map(String::as_str).collect();
+                let client = GtpClient::spawn(&name, &words[0], &args).map_err(|e| format!("{}: {}", words[0], e))?;
+                Ok(Box::new(client) as Box<dyn Player>)
+            })
+        }
+        None => {
+            let player = parse_engine(spec, config)?;
+            Arc::new(move || Ok(Box::new(player.clone()) as Box<dyn Player>))
+        }
+    };
+    Ok(Entrant { name: spec.to_string(), make })
+}
+
+fn run_tournament(args: &[String]) -> Result<(), String> {
+    let mut tournament_config = TournamentConfig::default();
+    let mut config = CpuConfig::default();
+    let mut record_path = None;
+    let mut specs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut count = |name: &str| -> Result<u32, String> {
+            let text = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+            text.parse().map_err(|_| format!("invalid {} '{}'", name, text))
+        };
+        match arg.as_str() {
+            "--gauntlet" => tournament_config.schedule = Schedule::Gauntlet,
+            "--games" => tournament_config.games = count("game count")?,
+            "--openings" => tournament_config.opening_plies = count("ply count")?,
+            "--jobs" => tournament_config.jobs = count("job count")?.max(1) as usize,
+            "--record" => record_path = Some(args.next().ok_or("--record needs a file")?),
+            _ if apply_cpu_option(&mut config, arg, &mut args)? => {}
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => specs.push(arg.clone()),
+        }
+    }
+    if specs.len() < 2 {
+        return Err(USAGE.to_string());
+    }
+    tournament_config.seed = config.seed;
+    let entrants = specs.iter().map(|spec| parse_entrant(spec, &config)).collect::<Result<Vec<_>, _>>()?;
+
+    let total = tournament::schedule(entrants.len(), &tournament_config).len();
+    println!("{} engines, {} games", entrants.len(), total);
+    let mut finished = 0;
+    let results = tournament::run(&entrants, &tournament_config, |game| {
+        finished += 1;
+        let forfeit = match game.outcome.forfeit {
+            Some(Disc::Black) => " (Black forfeits)",
+            Some(_) => " (White forfeits)",
+            None => "",
+        };
+        println!(
+            "  [{}/{}] game {}: {} {}-{} {}{}",
+            finished,
+            total,
+            game.index + 1,
+            specs[game.black],
+            game.outcome.result.black,
+            game.outcome.result.white,
+            specs[game.white],
+            forfeit
+        );
+    });
+
+    if let Some(path) = record_path {
+        let played: Vec<_> = results
+            .games
+            .iter()
+            .map(|game| (specs[game.black].as_str(), specs[game.white].as_str(), game.outcome.clone()))
+            .collect();
+        write_record(path, &played, &tournament_config.clock)?;
+    }
+
+    let table = results.crosstable();
+    let width = specs.iter().map(String::len).max().unwrap_or(0);
+    println!();
+    println!("Crosstable (wins-draws-losses for the row engine):");
+    print!("  {:>2} {:width$}", "", "");
+    for column in 1..=specs.len() {
+        print!(" {:>8}", column);
+    }
+    println!();
+    for (row, spec) in specs.iter().enumerate() {
+        print!("  {:>2} {:width$}", row + 1, spec);
+        for (column, cell) in table[row].iter().enumerate() {
+            if row == column || cell.games() == 0 {
+                print!(" {:>8}", "-");
+            } else {
+                print!(" {:>8}", format!("{}-{}-{}", cell.wins, cell.draws, cell.losses));
+            }
+        }
+        println!();
+    }
+
+    println!();
+    println!("Ratings (Elo relative to the field average):");
+    for (rank, standing) in results.standings().iter().enumerate() {
+        let stats = &standing.stats;
+        println!(
+            "  {:>2}. {:width$} {:>+6.0}  {:>5.1}/{:<3} +{} ={} -{}  avg {:+.1}",
+            rank + 1,
+            specs[standing.entrant],
+            standing.rating,
+            stats.wins as f64 + stats.draws as f64 / 2.0,
+            stats.games(),
+            stats.wins,
+            stats.draws,
+            stats.losses,
+            stats.average_margin()
+        );
+    }
+    Ok(())
+}
+
+fn run_book(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("show") => book_show(&args[1..]),
+        Some("build") => book_build(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn book_show(args: &[String]) -> Result<(), String> {
+    let mut book_path = None;
+    let mut transcript = String::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--book" => book_path = Some(args.next().ok_or("--book needs a file")?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => transcript.push_str(arg),
+        }
+    }
+
+    let book = match book_path {
+        Some(path) => Arc::new(Book::load(path).map_err(|e| format!("{}: {}", path, e))?),
+        None => Book::builtin(),
+    };
+
+    let game = replay(&transcript)?;
+    let side = if game.current_turn() == Disc::Black { "Black" } else { "White" };
+    println!("Position after '{}' ({} to move)", transcript, side);
+
+    let moves = book.moves(game.board(), game.current_turn());
+    if moves.is_empty() {
+        println!("  not in book");
+        return Ok(());
+    }
+    let total: u32 = moves.iter().map(|m| m.weight).sum();
+    for book_move in moves {
+        println!(
+            "  {}  weight {:>5}  {:5.1}%",
+            format_square(book_move.mv.0, book_move.mv.1),
+            book_move.weight,
+            100.0 * book_move.weight as f64 / total as f64
+        );
+    }
+    Ok(())
+}
+
+fn book_build(args: &[String]) -> Result<(), String> {
+    let mut plies = 20;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--plies" => {
+                let value = args.next().ok_or("--plies needs a number")?;
+                plies = value.parse().map_err(|_| format!("invalid ply count '{}'", value))?;
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => paths.push(arg),
+        }
+    }
+    let (output, inputs) = paths.split_first().ok_or(USAGE)?;
+    if inputs.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    // Extend an existing book rather than overwriting it.
+    let mut book = match fs::metadata(output) {
+        Ok(_) => Book::load(output).map_err(|e| format!("{}: {}", output, e))?,
+        Err(_) => Book::new(),
+    };
+    let mut added = 0;
+    for input in inputs {
+        if let Some(games) = load_game_database(input)? {
+            // Book lines start from the initial position.
+            let transcripts: Vec<String> = games
+                .iter()
+                .filter(|game| game.start_position() == (Board::new(), Disc::Black))
+                .map(Game::transcript)
+                .collect();
+            let games = transcripts.iter().map(String::as_str);
+            added += book.extend_from_games(games, plies).map_err(|e| format!("{}: {}", input, e))?;
+            continue;
+        }
+        let text = fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+        let games = text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+        added += book.extend_from_games(games, plies).map_err(|e| format!("{}: {}", input, e))?;
+    }
+    book.save(output).map_err(|e| format!("{}: {}", output, e))?;
+    println!("Added {} games; {} now holds {} positions", added, output, book.len());
+    Ok(())
+}
+
+fn run_train(args: &[String]) -> Result<(), String> {
+    let mut options = TrainOptions::default();
+    let mut weights = PatternWeights::new();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--epochs" => {
+                let text = value()?;
+                options.epochs = text.parse().map_err(|_| format!("invalid epoch count '{}'", text))?;
+            }
+            "--rate" => {
+                let text = value()?;
+                options.rate = text
+                    .parse()
+                    .ok()
+                    .filter(|rate| *rate > 0.0 && *rate <= 1.0)
+                    .ok_or_else(|| format!("invalid rate '{}' (expected more than 0, at most 1)", text))?;
+            }
+            "--seed" => {
+                let text = value()?;
+                options.seed = text.parse().map_err(|_| format!("invalid seed '{}'", text))?;
+            }
+            "--init" => {
+                let path = value()?;
+                weights = PatternWeights::load(path).map_err(|e| format!("{}: {}", path, e))?;
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => paths.push(arg),
+        }
+    }
+    let (output, inputs) = paths.split_first().ok_or(USAGE)?;
+    if inputs.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut samples = Vec::new();
+    let mut games = 0;
+    let mut skipped = 0;
+    for input in inputs {
+        if let Some(database) = load_game_database(input)? {
+            for game in database {
+                let positions = pattern::samples_from_game(&game);
+                if positions.is_empty() {
+                    skipped += 1;
+                } else {
+                    games += 1;
+                    samples.extend(positions);
+                }
+            }
+            continue;
+        }
+        let text = fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("{}:{}: {}", input, number + 1, message);
+            if let Some((position, score)) = line.split_once(';') {
+                let game = Game::from_position_str(position).map_err(|e| error(e.to_string()))?;
+                let margin = score.trim().parse().map_err(|_| error(format!("invalid score '{}'", score.trim())))?;
+                samples.push(Sample { board: *game.board(), player: game.current_turn(), margin });
+            } else {
+                let game = Game::from_transcript(line).map_err(|e| error(e.to_string()))?;
+                let positions = pattern::samples_from_game(&game);
+                if positions.is_empty() {
+                    skipped += 1;
+                } else {
+                    games += 1;
+                    samples.extend(positions);
+                }
+            }
+        }
+    }
+    if samples.is_empty() {
+        return Err("no training positions found".to_string());
+    }
+    println!("Training on {} positions ({} games, {} unfinished games skipped)", samples.len(), games, skipped);
+
+    let start = Instant::now();
+    let weights = pattern::train(weights, &samples, &options, |epoch, rmse| {
+        println!("  epoch {:>3}: error {:.2} discs ({:.1}s)", epoch, rmse, start.elapsed().as_secs_f64());
+    });
+    weights.save(output).map_err(|e| format!("{}: {}", output, e))?;
+    println!("Wrote {}; use it with --eval {}", output, output);
+    Ok(())
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path).extension().is_some_and(|found| found.eq_ignore_ascii_case(extension))
+}
+
+// The games of a WTHOR database (`.wtb`) or GGF file (`.ggf`), or `None` for any
+// other file.
+fn load_game_database(path: &str) -> Result<Option<Vec<Game>>, String> {
+    if has_extension(path, "wtb") {
+        Ok(Some(load_wthor(path)?.games.into_iter().map(|game| game.game).collect()))
+    } else if has_extension(path, "ggf") {
+        let games = ggf::load(path).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Some(games.into_iter().map(|record| record.game).collect()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn load_wthor(path: &str) -> Result<GameDatabase, String> {
+    GameDatabase::load(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn run_wthor(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("show") => wthor_show(&args[1..]),
+        Some("write") => wthor_write(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn wthor_show(args: &[String]) -> Result<(), String> {
+    let mut players = Vec::new();
+    let mut tournaments = Vec::new();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--players" | "--tournaments" => {
+                let path = args.next().ok_or_else(|| format!("{} needs a file", arg))?;
+                let kind = if arg == "--players" { NameKind::Players } else { NameKind::Tournaments };
+                let names = wthor::load_names(path, kind).map_err(|e| format!("{}: {}", path, e))?;
+                if kind == NameKind::Players {
+                    players = names;
+                } else {
+                    tournaments = names;
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let name =
+        |names: &[String], index: u16| names.get(index as usize).cloned().unwrap_or_else(|| format!("#{}", index));
+
+    let (mut black_wins, mut draws, mut white_wins, mut black_discs) = (0, 0, 0, 0u64);
+    for path in paths {
+        let database = load_wthor(path)?;
+        println!("{}: {} games from {}", path, database.games.len(), database.header.year);
+        for (index, game) in database.games.iter().enumerate() {
+            println!(
+                "  {:>5}: {} {}-{} {} ({}) {}",
+                index + 1,
+                name(&players, game.black),
+                game.black_discs,
+                64 - game.black_discs as u32,
+                name(&players, game.white),
+                name(&tournaments, game.tournament),
+                game.game.transcript()
+            );
+            match game.black_discs.cmp(&32) {
+                std::cmp::Ordering::Greater => black_wins += 1,
+                std::cmp::Ordering::Equal => draws += 1,
+                std::cmp::Ordering::Less => white_wins += 1,
+            }
+            black_discs += game.black_discs as u64;
+        }
+    }
+    let total = black_wins + draws + white_wins;
+    if total > 0 {
+        let percent = |count: u32| count as f64 * 100.0 / total as f64;
+        println!(
+            "{} games: Black wins {} ({:.1}%), draws {} ({:.1}%), White wins {} ({:.1}%); average {:.1}-{:.1}",
+            total,
+            black_wins,
+            percent(black_wins),
+            draws,
+            percent(draws),
+            white_wins,
+            percent(white_wins),
+            black_discs as f64 / total as f64,
+            64.0 - black_discs as f64 / total as f64
+        );
+    }
+    Ok(())
+}
+
+fn wthor_write(args: &[String]) -> Result<(), String> {
+    let mut year = wthor::Date::today().year;
+    let mut players_path = None;
+    let mut tournaments_path = None;
+    let mut tournament = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--year" => {
+                let text = args.next().ok_or("--year needs a value")?;
+                year = text.parse().map_err(|_| format!("invalid year '{}'", text))?;
+            }
+            "--players" => players_path = Some(args.next().ok_or("--players needs a file")?),
+            "--tournaments" => tournaments_path = Some(args.next().ok_or("--tournaments needs a file")?),
+            "--tournament" => tournament = Some(args.next().ok_or("--tournament needs a name")?.as_str()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => paths.push(arg),
+        }
+    }
+    let (output, inputs) = paths.split_first().ok_or(USAGE)?;
+    if inputs.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut database = GameDatabase::new(year);
+    let mut players = NameIndex::new();
+    let mut tournaments = NameIndex::new();
+    let default_tournament = tournaments.index(tournament)?;
+    for input in inputs {
+        if has_extension(input, "ggf") {
+            for record in ggf::load(input).map_err(|e| format!("{}: {}", input, e))? {
+                let tournament = match record.place.as_deref() {
+                    Some(place) => tournaments.index(Some(place))?,
+                    None => default_tournament,
+                };
+                let black = players.index(Some(&record.black.name))?;
+                let white = players.index(Some(&record.white.name))?;
+                database.games.push(WthorGame::new(record.game, tournament, black, white));
+            }
+            continue;
+        }
+        let text = fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let game = Game::from_transcript(line).map_err(|e| format!("{}:{}: {}", input, number + 1, e))?;
+            database.games.push(WthorGame::new(game, default_tournament, 0, 0));
+        }
+    }
+    database.save(output).map_err(|e| format!("{}: {}", output, e))?;
+    println!("Wrote {} games to {}", database.games.len(), output);
+    for (path, kind, index) in [
+        (players_path, NameKind::Players, &players),
+        (tournaments_path, NameKind::Tournaments, &tournaments),
+    ] {
+        if let Some(path) = path {
+            let mut file = fs::File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+            wthor::write_names(&mut file, kind, &index.names).map_err(|e| format!("{}: {}", path, e))?;
+            println!("Wrote {} names to {}", index.names.len(), path);
+        }
+    }
+    Ok(())
+}
+
+// Numbers player or tournament names in order of first appearance for a WTHOR name
+// file. Index 0 stands for an unknown name.
+struct NameIndex {
+    names: Vec<String>,
+    indices: HashMap<String, u16>,
+}
+
+impl NameIndex {
+    fn new() -> Self {
+        NameIndex { names: vec!["?".to_string()], indices: HashMap::from([("?".to_string(), 0)]) }
+    }
+
+    fn index(&mut self, name: Option<&str>) -> Result<u16, String> {
+        let name = name.map(str::trim).filter(|name| !name.is_empty()).unwrap_or("?");
+        if let Some(&index) = self.indices.get(name) {
+            return Ok(index);
+        }
+        let index = u16::try_from(self.names.len()).map_err(|_| "too many names for a WTHOR file".to_string())?;
+        self.names.push(name.to_string());
+        self.indices.insert(name.to_string(), index);
+        Ok(index)
+    }
+}
+
+fn run_perft(args: &[String]) -> Result<(), String> {
+    let mut game = Game::new();
+    let mut depth = None;
+    let mut divide = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divide" => divide = true,
+            "--moves" => game = replay(args.next().ok_or("--moves needs a transcript")?)?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => match arg.parse::<u32>() {
+                Ok(value) => depth = Some(value),
+                Err(_) => game = Game::from_position_str(arg).map_err(|e| format!("{}: {}", arg, e))?,
+            },
+        }
+    }
+    let depth = depth.ok_or(USAGE)?;
+
+    if divide {
+        let start = Instant::now();
+        let mut total = 0;
+        for (mv, nodes) in perft::divide(&game, depth) {
+            println!("{}: {}", mv, nodes);
+            total += nodes;
+        }
+        println!("Total: {} ({:.2}s)", total, start.elapsed().as_secs_f64());
+        return Ok(());
+    }
+
+    // Only the initial position has published counts to check against.
+    let initial = game.start_position() == (Board::new(), Disc::Black) && game.history().is_empty();
+    let mut mismatch = None;
+    for ply in 1..=depth {
+        let start = Instant::now();
+        let nodes = perft::perft(&game, ply);
+        let check = match perft::initial_count(ply).filter(|_| initial) {
+            Some(expected) if expected == nodes => " ok".to_string(),
+            Some(expected) => {
+                mismatch.get_or_insert(ply);
+                format!(" MISMATCH (expected {})", expected)
+            }
+            None => String::new(),
+        };
+        println!("depth {:>2}: {:>15} ({:.2}s){}", ply, nodes, start.elapsed().as_secs_f64(), check);
+    }
+    match mismatch {
+        Some(ply) => Err(format!("move generation disagrees with the known count at depth {}", ply)),
+        None => Ok(()),
+    }
+}
+
+fn run_analyze(args: &[String]) -> Result<(), String> {
+    let (game, limits, config) = parse_analyze_options(args)?;
+    let analysis = analysis::analyze(&game, &limits, config.evaluator.as_ref(), |done, total| {
+        eprint!("\rAnalyzing move {} of {}...", done, total);
+        true
+    });
+    eprint!("\r\x1b[K");
+    print_analysis(&analysis);
+    Ok(())
+}
+
+// The game to analyze, the search limits per position and the CPU options.
+fn parse_analyze_options(args: &[String]) -> Result<(Game, SearchLimits, CpuConfig), String> {
+    let mut config = CpuConfig::default();
+    let mut timed = false;
+    let mut depth = None;
+    let mut path = None;
+    let mut number = None;
+    let mut transcripts = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                let text = args.next().ok_or("--depth needs a value")?;
+                let parsed = text.parse().ok().filter(|depth| (1..=60).contains(depth));
+                depth = Some(parsed.ok_or_else(|| format!("invalid depth '{}'", text))?);
+            }
+            "--file" => path = Some(args.next().ok_or("--file needs a file")?),
+            "--game" => {
+                let text = args.next().ok_or("--game needs a value")?;
+                let parsed = text.parse().ok().filter(|number| *number > 0);
+                number = Some(parsed.ok_or_else(|| format!("invalid game '{}'", text))?);
+            }
+            "--time" => timed = apply_cpu_option(&mut config, arg, &mut args)?,
+            _ if apply_cpu_option(&mut config, arg, &mut args)? => {}
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+            _ => transcripts.push(arg),
+        }
+    }
+    let game = match (path, transcripts.as_slice()) {
+        (Some(path), []) => load_record(path, number.unwrap_or(1))?,
+        (None, [transcript]) if number.is_none() => Game::from_transcript(transcript).map_err(|e| e.to_string())?,
+        _ => return Err(USAGE.to_string()),
+    };
+    // A --time option replaces the fixed analysis depth.
+    let endgame_empties = config.limits.endgame_empties;
+    let limits = match depth {
+        Some(depth) => SearchLimits { endgame_empties, ..SearchLimits::depth(depth) },
+        None if timed => config.limits,
+        None => SearchLimits { endgame_empties, ..SearchLimits::depth(DEFAULT_ANALYSIS_DEPTH) },
+    };
+    Ok((game, limits, config))
+}
+
+fn print_analysis(analysis: &GameAnalysis) {
+    let side_name = |side: Disc| if side == Disc::Black { "Black" } else { "White" };
+    for analysis in &analysis.moves {
+        let judgement = analysis.judgement();
+        let mut line = format!(
+            "  {:>3}. {} {:<4} {:>+6.1}",
+            analysis.ply + 1,
+            side_name(analysis.player),
+            format!("{}{}", analysis.played, judgement.symbol()),
+            analysis.played_score
+        );
+        if analysis.loss() > 0.0 {
+            let (best, best_score, loss) = (analysis.best, analysis.best_score, analysis.loss());
+            line.push_str(&format!("   best {} {:+.1}, loses {:.1}", best, best_score, loss));
+        }
+        if judgement >= Judgement::Inaccuracy {
+            line.push_str(&format!(" ({})", format!("{:?}", judgement).to_lowercase()));
+        }
+        println!("{}", line);
+    }
+    println!();
+    for side in [Disc::Black, Disc::White] {
+        let summary = analysis.summary(side);
+        println!(
+            "{}: {} moves, average loss {:.2} discs, {} inaccuracies, {} mistakes, {} blunders",
+            side_name(side),
+            summary.moves,
+            summary.average_loss(),
+            summary.inaccuracies,
+            summary.mistakes,
+            summary.blunders
+        );
+    }
+    if let Some(result) = analysis.result {
+        println!("Result: Black {}-{} White", result.black, result.white);
+    }
+
+    let (rows, scale) = analysis::score_graph(&analysis.black_scores(), 4);
+    println!();
+    println!("Black's expected final margin after each move:");
+    let middle = rows.len() / 2;
+    for (index, row) in rows.iter().enumerate() {
+        let label = match index {
+            0 => format!("{:+}", scale),
+            _ if index == middle => "0".to_string(),
+            _ if index == rows.len() - 1 => format!("{:+}", -scale),
+            _ => String::new(),
+        };
+        let axis = if index == middle { '┼' } else { '│' };
+        println!("{:>4} {}{}", label, axis, row);
+    }
+}
+
+// Beyond this many empty squares a perfect solve can take hours.
+const MAX_SOLVE_EMPTIES: u32 = 24;
+
+fn run_solve(args: &[String]) -> Result<(), String> {
+    let mut mode = SolveMode::Exact;
+    let mut force = false;
+    let mut positions = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--wld" => mode = SolveMode::WinLossDraw,
+            "--force" => force = true,
+            "--file" => {
+                let path = args.next().ok_or("--file needs a file")?;
+                let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+                positions.extend(text.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from));
+            }
+            // Positions may start with "--" too, but always have a space before the side to move.
+            _ if arg.starts_with("--") && !arg.contains(' ') => {
+                return Err(format!("unknown option '{}'\n\n{}", arg, USAGE));
+            }
+            _ => positions.push(arg.clone()),
+        }
+    }
+    if positions.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    // Check every position first so a bad one doesn't stop a long run halfway.
+    let mut games = Vec::with_capacity(positions.len());
+    for (number, position) in positions.iter().enumerate() {
+        let game = Game::from_position_str(position).map_err(|e| format!("position {}: {}", number + 1, e))?;
+        let empties = game.board().count(Disc::Empty);
+        if empties > MAX_SOLVE_EMPTIES && !force {
+            return Err(format!(
+                "position {}: {} empty squares is too many to solve (at most {}; use --force to try anyway)",
+                number + 1,
+                empties,
+                MAX_SOLVE_EMPTIES
+            ));
+        }
+        games.push(game);
+    }
+
+    for (number, game) in games.iter().enumerate() {
+        let started = Instant::now();
+        let solution = endgame::solve(game.board(), game.current_turn(), mode);
+        let best_move = solution.best_move.map_or("pass".to_string(), |(r, c)| format_square(r, c));
+        let score = match (mode, solution.score.signum()) {
+            (SolveMode::Exact, _) => format!("{:+}", solution.score),
+            (SolveMode::WinLossDraw, 1) => "win".to_string(),
+            (SolveMode::WinLossDraw, 0) => "draw".to_string(),
+            (SolveMode::WinLossDraw, _) => "loss".to_string(),
+        };
+        println!(
+            "#{:<3} {}  {:>4}  {:>12} nodes  {:.3}s",
+            number + 1,
+            best_move,
+            score,
+            solution.nodes,
+            started.elapsed().as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+fn replay(transcript: &str) -> Result<Game, String> {
+    Game::from_transcript(transcript).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_play_options() {
+        let options = parse_play_options(&args("--black hard --moves f5d6 --seed 7 --no-book --delay 0.5")).unwrap();
+        assert_eq!(options.black, PlayerType::Cpu(CpuLevel::Hard));
+        assert_eq!(options.white, PlayerType::Human);
+        assert_eq!(options.game.transcript(), "f5d6");
+        assert_eq!(options.config.seed, Some(7));
+        assert!(options.config.book.is_none());
+        assert_eq!(options.delay, Duration::from_millis(500));
+        assert_eq!(options.clock, Clock::unlimited());
+        assert_eq!(options.cpu_options, args("--seed 7 --no-book"));
+        let options = parse_play_options(&args("--time 3 --book books/openings.txt")).unwrap();
+        assert_eq!(options.cpu_options[..3], args("--time 3 --book"));
+        assert!(Path::new(&options.cpu_options[3]).is_absolute());
+        assert!(options.cpu_options[3].ends_with("openings.txt"));
+    }
+
+    #[test]
+    fn test_parse_clock() {
+        let options = parse_play_options(&args("--white hard --clock 5+3")).unwrap();
+        assert_eq!(options.clock, Clock::total(Duration::from_secs(300), Duration::from_secs(3)));
+        assert_eq!(parse_clock("0.5"), Ok(Clock::total(Duration::from_secs(30), Duration::ZERO)));
+        for text in ["0", "+3", "5+", "-1", "5+x"] {
+            assert!(parse_clock(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_play_options_replay() {
+        let path = std::env::temp_dir().join(format!("othello-replay-{}.txt", std::process::id()));
+        fs::write(&path, "# two games\nf5d6\n\nf5f6e6\n").unwrap();
+        let path = path.to_str().unwrap();
+        let options = parse_play_options(&args(&format!("--replay {} --game 2", path))).unwrap();
+        assert_eq!(options.replay.map(|game| game.transcript()).as_deref(), Some("f5f6e6"));
+        assert!(parse_play_options(&args(&format!("--replay {}", path))).unwrap().replay.is_some());
+        assert!(parse_play_options(&args(&format!("--replay {} --game 3", path))).is_err());
+        assert!(parse_play_options(&args(&format!("--replay {} --game 0", path))).is_err());
+        assert!(parse_play_options(&args("--game 1")).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_play_options_errors() {
+        assert!(parse_play_options(&args("--white expert")).is_err());
+        assert!(parse_play_options(&args("--time")).is_err());
+        assert!(parse_play_options(&args("--time -1")).is_err());
+        assert!(parse_play_options(&args("--delay -1")).is_err());
+        assert!(parse_play_options(&args("--moves a1")).is_err());
+        assert!(parse_play_options(&args("--unknown")).is_err());
+    }
+
+    #[test]
+    fn test_is_play_options() {
+        assert!(is_play_options(&args("--black hard")));
+        assert!(!is_play_options(&args("--help")));
+        assert!(!is_play_options(&args("book show")));
+        assert!(!is_play_options(&[]));
+    }
+
+    #[test]
+    fn test_book_rejects_unknown_options() {
+        let error = book_show(&args("--boook x.bin f5d6")).unwrap_err();
+        assert!(error.starts_with("unknown option '--boook'"), "{}", error);
+        let error = book_build(&args("--plise 10 book.bin games.txt")).unwrap_err();
+        assert!(error.starts_with("unknown option '--plise'"), "{}", error);
+    }
+
+    #[test]
+    fn test_wthor_write_names() {
+        let dir = std::env::temp_dir().join(format!("othello-wthor-write-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let mut records = GgfGame::new(Game::from_transcript("f5d6").unwrap(), "alice", "bob").to_string();
+        let mut second = GgfGame::new(Game::from_transcript("f5f6").unwrap(), "bob", "carol");
+        second.place = Some("Club".to_string());
+        records.push_str(&second.to_string());
+        fs::write(path("games.ggf"), records).unwrap();
+        fs::write(path("games.txt"), "f5d6c3\n").unwrap();
+
+        let command = format!(
+            "--players {} --tournaments {} --tournament Open {} {} {}",
+            path("names.JOU"),
+            path("names.TRN"),
+            path("out.wtb"),
+            path("games.ggf"),
+            path("games.txt")
+        );
+        wthor_write(&args(&command)).unwrap();
+        let database = GameDatabase::load(path("out.wtb")).unwrap();
+        let indices: Vec<_> = database.games.iter().map(|game| (game.tournament, game.black, game.white)).collect();
+        assert_eq!(indices, [(1, 1, 2), (2, 2, 3), (1, 0, 0)]);
+        let players = wthor::load_names(path("names.JOU"), NameKind::Players).unwrap();
+        assert_eq!(players, ["?", "alice", "bob", "carol"]);
+        let tournaments = wthor::load_names(path("names.TRN"), NameKind::Tournaments).unwrap();
+        assert_eq!(tournaments, ["?", "Open", "Club"]);
+
+        let error = wthor_write(&args(&format!("--output {} {}", path("x.wtb"), path("games.txt")))).unwrap_err();
+        assert!(error.starts_with("unknown option '--output'"), "{}", error);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_analyze_options() {
+        let (game, limits, _) = parse_analyze_options(&args("f5d6c3")).unwrap();
+        assert_eq!(game.transcript(), "f5d6c3");
+        assert_eq!(limits, SearchLimits::depth(DEFAULT_ANALYSIS_DEPTH));
+        // The same as the CPU's default thinking time, but still asked for.
+        let (_, limits, _) = parse_analyze_options(&args("--time 2 f5d6c3")).unwrap();
+        assert_eq!(limits, SearchLimits::time(Duration::from_secs(2)));
+        let (_, limits, _) = parse_analyze_options(&args("--depth 4 --time 2 f5d6c3")).unwrap();
+        assert_eq!(limits, SearchLimits::depth(4));
+        let (_, limits, _) = parse_analyze_options(&args("--endgame 16 --depth 4 f5d6c3")).unwrap();
+        assert_eq!(limits, SearchLimits { endgame_empties: 16, ..SearchLimits::depth(4) });
+        let (_, limits, _) = parse_analyze_options(&args("--endgame 0 --time 2 f5d6c3")).unwrap();
+        assert_eq!(limits, SearchLimits { endgame_empties: 0, ..SearchLimits::time(Duration::from_secs(2)) });
+        assert!(parse_analyze_options(&args("--endgame 61 f5d6c3")).is_err());
+        assert!(parse_analyze_options(&args("--depth 0 f5d6c3")).is_err());
+        assert!(parse_analyze_options(&args("f5d6c3 --game 2")).is_err());
+    }
+
+    #[test]
+    fn test_solve_refuses_early_positions() {
+        let start = "---------------------------OX------XO--------------------------- X";
+        let error = run_solve(&[start.to_string()]).unwrap_err();
+        assert!(error.contains("60 empty squares"), "{}", error);
+        assert!(error.contains("--force"));
+    }
+
+    #[test]
+    fn test_solve_rejects_unknown_options() {
+        let position = "--OOOOOOXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX X";
+        let error = run_solve(&["--wdl".to_string(), position.to_string()]).unwrap_err();
+        assert!(error.starts_with("unknown option '--wdl'"), "{}", error);
+        assert!(run_solve(&["--wld".to_string(), position.to_string()]).is_ok());
+    }
+}
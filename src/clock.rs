@@ -0,0 +1,147 @@
+//! Game clocks: untimed play, a fixed budget per move, or a total per side with
+//! an optional increment.
+
+use crate::board::Disc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    Unlimited,
+    /// Every move may take up to this long.
+    PerMove(Duration),
+    /// Each side has `time` for the whole game and gains `increment` after each move.
+    Total { time: Duration, increment: Duration },
+}
+
+/// Remaining time for both sides under a `TimeControl`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clock {
+    control: TimeControl,
+    black: Duration,
+    white: Duration,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let time = match control {
+            TimeControl::Total { time, .. } => time,
+            _ => Duration::ZERO,
+        };
+        Clock { control, black: time, white: time }
+    }
+
+    pub fn unlimited() -> Self {
+        Clock::new(TimeControl::Unlimited)
+    }
+
+    pub fn per_move(time: Duration) -> Self {
+        Clock::new(TimeControl::PerMove(time))
+    }
+
+    pub fn total(time: Duration, increment: Duration) -> Self {
+        Clock::new(TimeControl::Total { time, increment })
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Time left for `side` under a `Total` control, `None` otherwise.
+    pub fn remaining(&self, side: Disc) -> Option<Duration> {
+        match self.control {
+            TimeControl::Total { .. } => Some(if side == Disc::Black { self.black } else { self.white }),
+            _ => None,
+        }
+    }
+
+    /// Overrides the time left for `side`, e.g. when a server reports its own clock.
+    pub fn set_remaining(&mut self, side: Disc, time: Duration) {
+        if side == Disc::Black {
+            self.black = time;
+        } else {
+            self.white = time;
+        }
+    }
+
+    /// How long `side` should think about its next move with `empties` empty
+    /// squares left, or `None` when the game is untimed. Under a total time control
+    /// the remaining time is spread over the moves still to play, and no single
+    /// move gets more than half of it.
+    pub fn budget(&self, side: Disc, empties: u32) -> Option<Duration> {
+        match self.control {
+            TimeControl::Unlimited => None,
+            TimeControl::PerMove(time) => Some(time),
+            TimeControl::Total { increment, .. } => {
+                let remaining = self.remaining(side)?;
+                let moves_left = empties.div_ceil(2).max(1);
+                Some((remaining / (moves_left + 2) + increment).min(remaining / 2))
+            }
+        }
+    }
+
+    /// Charges `elapsed` to `side` and returns false if it overstepped the time
+    /// control.
+    pub fn spend(&mut self, side: Disc, elapsed: Duration) -> bool {
+        match self.control {
+            TimeControl::Unlimited => true,
+            TimeControl::PerMove(time) => elapsed <= time,
+            TimeControl::Total { increment, .. } => {
+                let remaining = if side == Disc::Black { &mut self.black } else { &mut self.white };
+                match remaining.checked_sub(elapsed) {
+                    Some(left) => {
+                        *remaining = left + increment;
+                        true
+                    }
+                    None => {
+                        *remaining = Duration::ZERO;
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::unlimited()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_clock() {
+        let mut clock = Clock::unlimited();
+        assert_eq!(clock.budget(Disc::Black, 60), None);
+        assert_eq!(clock.remaining(Disc::Black), None);
+        assert!(clock.spend(Disc::Black, Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_per_move_clock() {
+        let mut clock = Clock::per_move(Duration::from_secs(2));
+        assert_eq!(clock.budget(Disc::White, 30), Some(Duration::from_secs(2)));
+        assert!(clock.spend(Disc::White, Duration::from_secs(2)));
+        assert!(!clock.spend(Disc::White, Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_total_clock() {
+        let mut clock = Clock::total(Duration::from_secs(60), Duration::from_secs(1));
+        assert!(clock.spend(Disc::Black, Duration::from_secs(10)));
+        assert_eq!(clock.remaining(Disc::Black), Some(Duration::from_secs(51)));
+        assert_eq!(clock.remaining(Disc::White), Some(Duration::from_secs(60)));
+
+        let budget = clock.budget(Disc::White, 60).unwrap();
+        assert!(budget > Duration::ZERO && budget <= Duration::from_secs(30));
+        // Short on time, the budget is capped at half of what is left.
+        clock.set_remaining(Disc::White, Duration::from_secs(2));
+        assert_eq!(clock.budget(Disc::White, 1), Some(Duration::from_secs(1)));
+
+        assert!(!clock.spend(Disc::White, Duration::from_secs(61)));
+        assert_eq!(clock.remaining(Disc::White), Some(Duration::ZERO));
+    }
+}
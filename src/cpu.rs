@@ -1,23 +1,104 @@
-use crate::game::Game;
-use crate::board::Disc;
+use crate::book::Book;
+use crate::game::{Game, GameResult};
+use crate::board::{squares, Board, Disc};
+use crate::endgame::{self, SolveMode, DEFAULT_ENDGAME_EMPTIES};
+use crate::eval::{Evaluator, Weights};
 use crate::player::CpuLevel;
-use rand::Rng;
+use crate::tt::{Bound, TranspositionTable, TtEntry};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Settings shared by all CPU levels.
+#[derive(Debug, Clone)]
+pub struct CpuConfig {
+    /// Search budget for the Hard level.
+    pub limits: SearchLimits,
+    /// Opening book to play from, or `None` to always think.
+    pub book: Option<Arc<Book>>,
+    /// Makes random choices (Easy moves, book moves) reproducible. The same seed
+    /// always picks the same move in the same position.
+    pub seed: Option<u64>,
+    /// Static evaluation for the Hard search.
+    pub evaluator: Arc<dyn Evaluator>,
+    /// Size of the Hard search's transposition table in megabytes.
+    pub tt_size_mb: usize,
+}
+
+impl Default for CpuConfig {
+    fn default() -> Self {
+        CpuConfig {
+            limits: SearchLimits::default(),
+            book: Some(Book::builtin()),
+            seed: None,
+            evaluator: Weights::builtin(),
+            tt_size_mb: DEFAULT_TT_SIZE_MB,
+        }
+    }
+}
+
+impl CpuConfig {
+    fn rng(&self, game: &Game, player: Disc) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ game.board().hash(player)),
+            None => StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    /// A weighted random move from the configured book, if the position is in it.
+    pub fn book_move(&self, game: &Game, player: Disc) -> Option<(usize, usize)> {
+        let book = self.book.as_ref()?;
+        book.choose(game.board(), player, &mut self.rng(game, player))
+    }
+}
 
 pub fn get_best_move(game: &Game, player: Disc, level: CpuLevel) -> (usize, usize) {
+    get_best_move_with(game, player, level, &CpuConfig::default())
+}
+
+/// Plays a weighted random move from the configured book when the position is in
+/// it, and otherwise thinks according to `level`.
+///
+/// A Hard search allocates a new `config.tt_size_mb` table on every call and
+/// forgets it afterwards. To keep the table from move to move, play through
+/// [`CpuPlayer`](crate::CpuPlayer) or pass your own to [`get_best_move_and_result`].
+pub fn get_best_move_with(game: &Game, player: Disc, level: CpuLevel, config: &CpuConfig) -> (usize, usize) {
+    // Only the Hard search uses the table.
+    let size_mb = if level == CpuLevel::Hard { config.tt_size_mb } else { 0 };
+    get_best_move_and_result(game, player, level, config, &mut TranspositionTable::new(size_mb)).0
+}
+
+/// Like [`get_best_move_with`], but also returns the search behind a Hard move,
+/// and the Hard search reuses `table` so results carry over between moves. Book
+/// moves and the other levels come without a search.
+pub fn get_best_move_and_result(
+    game: &Game,
+    player: Disc,
+    level: CpuLevel,
+    config: &CpuConfig,
+    table: &mut TranspositionTable,
+) -> ((usize, usize), Option<SearchResult>) {
     let valid_moves = game.get_valid_moves(player);
     if valid_moves.is_empty() {
-        return (0, 0); // Should not happen if called correctly
+        return ((0, 0), None); // Should not happen if called correctly
+    }
+
+    if let Some(book_move) = config.book_move(game, player) {
+        return (book_move, None);
     }
 
     match level {
-        CpuLevel::Easy => get_random_move(&valid_moves),
-        CpuLevel::Medium => get_greedy_move(game, &valid_moves, player),
-        CpuLevel::Hard => get_minimax_move(game, &valid_moves, player),
+        CpuLevel::Easy => (get_random_move(&valid_moves, &mut config.rng(game, player)), None),
+        CpuLevel::Medium => (get_greedy_move(game, &valid_moves, player), None),
+        CpuLevel::Hard => {
+            let result = search_with_table(game, player, &config.limits, config.evaluator.as_ref(), table);
+            (result.map_or((0, 0), |result| result.best_move), result)
+        }
     }
 }
 
-fn get_random_move(valid_moves: &[(usize, usize)]) -> (usize, usize) {
-    let mut rng = rand::rng();
+fn get_random_move<R: Rng + ?Sized>(valid_moves: &[(usize, usize)], rng: &mut R) -> (usize, usize) {
     let index = rng.random_range(0..valid_moves.len());
     valid_moves[index]
 }
@@ -45,110 +126,371 @@ fn get_greedy_move(game: &Game, valid_moves: &[(usize, usize)], player: Disc) ->
     best_move
 }
 
-fn get_minimax_move(game: &Game, valid_moves: &[(usize, usize)], player: Disc) -> (usize, usize) {
-    let mut best_move = valid_moves[0];
-    let mut best_score = i32::MIN;
+/// Score for a finished game, in units of final disc difference.
+const WIN_SCALE: i32 = 1000;
+/// Roughly how many evaluation points one disc of final margin is worth: a corner
+/// (100 points) tends to be worth about ten discs.
+pub const EVAL_PER_DISC: f64 = 10.0;
+const INFINITY: i32 = i32::MAX - 1;
 
-    for &(r, c) in valid_moves {
-        let mut temp_game = game.clone();
-        temp_game.make_move(r, c, player);
-        let score = minimax(&temp_game, 4, false, player, i32::MIN, i32::MAX);
-        
-        if score > best_score {
-            best_score = score;
-            best_move = (r, c);
+/// Bounds on a Hard search. The search deepens one ply at a time until it reaches
+/// `max_depth`, runs out of `time`, or has visited `nodes` positions, and then plays
+/// the best move of the deepest completed iteration. With `endgame_empties` or fewer
+/// empty squares left, the position is solved exactly instead, within the same
+/// budget; a solve that runs out falls back to the deepening search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub max_depth: u32,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+    pub endgame_empties: u32,
+}
+
+impl SearchLimits {
+    pub fn depth(max_depth: u32) -> Self {
+        SearchLimits { max_depth, time: None, nodes: None, endgame_empties: DEFAULT_ENDGAME_EMPTIES }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        SearchLimits { time: Some(time), ..SearchLimits::depth(60) }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        SearchLimits { nodes: Some(nodes), ..SearchLimits::depth(60) }
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits::time(Duration::from_millis(2000))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: (usize, usize),
+    /// Score from the searching player's point of view.
+    pub score: i32,
+    /// Depth of the deepest completed iteration.
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    /// True if every line was searched to the end of the game, so the score is
+    /// the final result under perfect play.
+    pub exact: bool,
+}
+
+impl SearchResult {
+    /// True if the score is a proven final result rather than an evaluation.
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    /// The score as a final disc margin for the searching player. Exact for proven
+    /// results, a rough estimate otherwise.
+    pub fn disc_margin(&self) -> f64 {
+        // Evaluations stay below WIN_SCALE, so larger scores are proven wins and
+        // losses even when the search stopped before the end of every line.
+        if self.exact || self.score.abs() >= WIN_SCALE {
+            (self.score / WIN_SCALE) as f64
+        } else {
+            self.score as f64 / EVAL_PER_DISC
         }
     }
+}
 
-    best_move
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
+
+/// Iterative deepening alpha-beta search with the built-in evaluation. Returns
+/// `None` if `player` has no valid move.
+pub fn search(game: &Game, player: Disc, limits: &SearchLimits) -> Option<SearchResult> {
+    let mut table = TranspositionTable::new(DEFAULT_TT_SIZE_MB);
+    search_with_table(game, player, limits, Weights::builtin().as_ref(), &mut table)
 }
 
-fn minimax(game: &Game, depth: i32, is_maximizing: bool, player: Disc, alpha: i32, beta: i32) -> i32 {
-    if depth == 0 || game.is_game_over() {
-        return evaluate_board(game, player);
+/// Like [`search`], but scores leaves with `evaluator` and reuses `table` so
+/// results carry over between searches.
+pub fn search_with_table(
+    game: &Game,
+    player: Disc,
+    limits: &SearchLimits,
+    evaluator: &dyn Evaluator,
+    table: &mut TranspositionTable,
+) -> Option<SearchResult> {
+    let board = *game.board();
+    let mut root_moves: Vec<(usize, usize)> = squares(board.legal_moves(player)).collect();
+    if root_moves.is_empty() {
+        return None;
     }
 
-    let current_player = if is_maximizing { player } else { get_opponent(player) };
-    let valid_moves = game.get_valid_moves(current_player);
+    let start = Instant::now();
+    let deadline = limits.time.map(|time| start + time);
+    let empties = board.count(Disc::Empty);
+    let mut solver_nodes = 0;
+    if empties <= limits.endgame_empties {
+        match endgame::solve_within(&board, player, SolveMode::Exact, deadline, limits.nodes) {
+            Ok(solution) => {
+                return Some(SearchResult {
+                    best_move: solution.best_move.unwrap_or(root_moves[0]),
+                    score: solution.score * WIN_SCALE,
+                    depth: empties,
+                    nodes: solution.nodes,
+                    elapsed: start.elapsed(),
+                    exact: true,
+                })
+            }
+            // Out of budget: the midgame search below still completes its first
+            // iteration, so there is a move to play.
+            Err(nodes) => solver_nodes = nodes,
+        }
+    }
 
-    if valid_moves.is_empty() {
-        return minimax(game, depth - 1, !is_maximizing, player, alpha, beta);
+    let mut searcher = Searcher {
+        deadline,
+        node_limit: limits.nodes,
+        nodes: solver_nodes,
+        aborted: false,
+        table,
+        evaluator,
+    };
+    let mut result = SearchResult {
+        best_move: root_moves[0],
+        score: searcher.evaluate(&board, player),
+        depth: 0,
+        nodes: 0,
+        elapsed: Duration::ZERO,
+        exact: false,
+    };
+
+    for depth in 1..=limits.max_depth.max(1) {
+        if root_moves.len() == 1 && depth > 1 {
+            break;
+        }
+        // The first iteration always completes so there is a move to play.
+        let Some((best_move, score)) = searcher.search_root(&board, player, &root_moves, depth, depth > 1) else {
+            break;
+        };
+        result.best_move = best_move;
+        result.score = score;
+        result.depth = depth;
+        // Passes don't use up depth, so this deep every line reaches the end.
+        result.exact = depth >= empties;
+
+        // Search the previous best move first in the next iteration.
+        let index = root_moves.iter().position(|&mv| mv == best_move).unwrap();
+        root_moves[..=index].rotate_right(1);
+
+        if depth >= empties || score.abs() >= WIN_SCALE {
+            break;
+        }
     }
 
-    let mut alpha = alpha;
-    let mut beta = beta;
+    result.nodes = searcher.nodes;
+    result.elapsed = start.elapsed();
+    Some(result)
+}
 
-    if is_maximizing {
-        let mut max_score = i32::MIN;
-        for &(r, c) in &valid_moves {
-            let mut temp_game = game.clone();
-            temp_game.make_move(r, c, current_player);
-            let score = minimax(&temp_game, depth - 1, false, player, alpha, beta);
-            max_score = max_score.max(score);
-            alpha = alpha.max(score);
-            if beta <= alpha {
-                break;
+/// Scores every legal move of the side to move, best first. Each result's
+/// `best_move` is the move it scores, and its score is from the mover's point of
+/// view. The positions after each move are searched one ply shallower, sharing
+/// `limits.time` and `limits.nodes` equally.
+pub fn rank_moves(
+    game: &Game,
+    limits: &SearchLimits,
+    evaluator: &dyn Evaluator,
+    table: &mut TranspositionTable,
+) -> Vec<SearchResult> {
+    let player = game.current_turn();
+    let moves = game.get_valid_moves(player);
+    let share = moves.len().max(1) as u32;
+    let child_limits = SearchLimits {
+        max_depth: limits.max_depth.saturating_sub(1).max(1),
+        time: limits.time.map(|time| time / share),
+        nodes: limits.nodes.map(|nodes| nodes / share as u64),
+        endgame_empties: limits.endgame_empties,
+    };
+
+    let mut results: Vec<SearchResult> = moves
+        .into_iter()
+        .map(|(row, col)| {
+            let start = Instant::now();
+            let mut child = game.clone();
+            child.make_move(row, col, player);
+            let mut result = if child.is_game_over() {
+                SearchResult {
+                    best_move: (row, col),
+                    score: final_score(child.board(), player),
+                    depth: 0,
+                    nodes: 0,
+                    elapsed: Duration::ZERO,
+                    exact: true,
+                }
+            } else if child.must_pass() {
+                child.pass();
+                search_with_table(&child, player, &child_limits, evaluator, table).expect("side to move has a move")
+            } else {
+                let mut reply = search_with_table(&child, get_opponent(player), &child_limits, evaluator, table)
+                    .expect("side to move has a move");
+                reply.score = -reply.score;
+                reply
+            };
+            result.best_move = (row, col);
+            result.depth += 1;
+            result.elapsed = start.elapsed();
+            result
+        })
+        .collect();
+    results.sort_by_key(|result| -result.score);
+    results
+}
+
+struct Searcher<'a> {
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    nodes: u64,
+    aborted: bool,
+    table: &'a mut TranspositionTable,
+    evaluator: &'a dyn Evaluator,
+}
+
+impl Searcher<'_> {
+    // Evaluations are kept below WIN_SCALE, whatever the weights, so that they are
+    // never mistaken for the result of a finished game.
+    fn evaluate(&self, board: &Board, player: Disc) -> i32 {
+        self.evaluator.evaluate(board, player).clamp(-(WIN_SCALE - 1), WIN_SCALE - 1)
+    }
+
+    fn out_of_budget(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        if self.node_limit.is_some_and(|limit| self.nodes >= limit) {
+            self.aborted = true;
+        }
+        if self.nodes.is_multiple_of(1024) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    fn search_root(
+        &mut self,
+        board: &Board,
+        player: Disc,
+        moves: &[(usize, usize)],
+        depth: u32,
+        can_abort: bool,
+    ) -> Option<((usize, usize), i32)> {
+        let mut best = (moves[0], -INFINITY);
+        let mut alpha = -INFINITY;
+        for &(r, c) in moves {
+            let mut next = *board;
+            next.apply_move(r, c, player);
+            let score = -self.minimax(&next, player.opponent(), depth - 1, -INFINITY, -alpha, false, can_abort);
+            if can_abort && self.aborted {
+                return None;
+            }
+            if score > best.1 {
+                best = ((r, c), score);
+                alpha = alpha.max(score);
             }
         }
-        max_score
-    } else {
-        let mut min_score = i32::MAX;
-        for &(r, c) in &valid_moves {
-            let mut temp_game = game.clone();
-            temp_game.make_move(r, c, current_player);
-            let score = minimax(&temp_game, depth - 1, true, player, alpha, beta);
-            min_score = min_score.min(score);
-            beta = beta.min(score);
-            if beta <= alpha {
-                break;
+        Some(best)
+    }
+
+    // Negamax alpha-beta: scores are from the point of view of `player`, the side to move.
+    #[allow(clippy::too_many_arguments)]
+    fn minimax(
+        &mut self,
+        board: &Board,
+        player: Disc,
+        depth: u32,
+        alpha: i32,
+        beta: i32,
+        passed: bool,
+        can_abort: bool,
+    ) -> i32 {
+        self.nodes += 1;
+        if can_abort && self.out_of_budget() {
+            return 0;
+        }
+
+        let moves = board.legal_moves(player);
+        if moves == 0 {
+            if passed {
+                return final_score(board, player);
             }
+            return -self.minimax(board, player.opponent(), depth, -beta, -alpha, true, can_abort);
         }
-        min_score
-    }
-}
-
-fn evaluate_board(game: &Game, player: Disc) -> i32 {
-    let (black_count, white_count) = game.count_discs();
-    let my_count = if player == Disc::Black { black_count } else { white_count };
-    let opponent_count = if player == Disc::Black { white_count } else { black_count };
-    
-    let disc_diff = my_count as i32 - opponent_count as i32;
-    let mobility = game.get_valid_moves(player).len() as i32 - 
-                   game.get_valid_moves(get_opponent(player)).len() as i32;
-    
-    let positional_score = calculate_positional_score(game, player);
-    
-    disc_diff + mobility * 5 + positional_score
-}
-
-fn calculate_positional_score(game: &Game, player: Disc) -> i32 {
-    let position_values = [
-        [100, -20, 10, 5, 5, 10, -20, 100],
-        [-20, -50, -2, -2, -2, -2, -50, -20],
-        [10, -2, -1, -1, -1, -1, -2, 10],
-        [5, -2, -1, -1, -1, -1, -2, 5],
-        [5, -2, -1, -1, -1, -1, -2, 5],
-        [10, -2, -1, -1, -1, -1, -2, 10],
-        [-20, -50, -2, -2, -2, -2, -50, -20],
-        [100, -20, 10, 5, 5, 10, -20, 100],
-    ];
-
-    let mut score = 0;
-    for r in 0..8 {
-        for c in 0..8 {
-            if let Some(disc) = game.board().get_disc(r, c) {
-                if disc == player {
-                    score += position_values[r][c];
-                } else if disc == get_opponent(player) {
-                    score -= position_values[r][c];
-                }
+        if depth == 0 {
+            return self.evaluate(board, player);
+        }
+
+        let key = board.hash(player);
+        let entry = self.table.probe(key);
+        let mut alpha = alpha;
+        let mut beta = beta;
+        if let Some(entry) = entry.filter(|entry| u32::from(entry.depth) >= depth) {
+            match entry.bound {
+                Bound::Exact => return entry.score,
+                Bound::Lower => alpha = alpha.max(entry.score),
+                Bound::Upper => beta = beta.min(entry.score),
+            }
+            if alpha >= beta {
+                return entry.score;
             }
         }
+
+        // Try the best move from an earlier search of this position first.
+        let first = entry
+            .and_then(|entry| entry.best_move)
+            .map(|square| 1u64 << square)
+            .filter(|bit| moves & bit != 0)
+            .unwrap_or(0);
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        for (r, c) in squares(first).chain(squares(moves & !first)) {
+            let mut next = *board;
+            next.apply_move(r, c, player);
+            let score = -self.minimax(&next, player.opponent(), depth - 1, -beta, -alpha, false, can_abort);
+            if self.aborted {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                best_move = Some((r * 8 + c) as u8);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.store(TtEntry { key, depth: depth.min(u8::MAX as u32) as u8, score: best, bound, best_move });
+        best
     }
-    score
 }
 
-fn get_opponent(player: Disc) -> Disc {
+fn final_score(board: &Board, player: Disc) -> i32 {
+    let result = GameResult::from_counts(board.count(Disc::Black), board.count(Disc::White));
+    result.margin(player) * WIN_SCALE
+}
+
+/// Scores `board` for `player` with the built-in evaluation weights.
+pub fn evaluate_board(board: &Board, player: Disc) -> i32 {
+    Weights::builtin().evaluate(board, player)
+}
+
+pub fn get_opponent(player: Disc) -> Disc {
     if player == Disc::Black { Disc::White } else { Disc::Black }
 }
 
@@ -165,7 +507,7 @@ mod tests {
     #[test]
     fn test_get_random_move() {
         let valid_moves = vec![(0, 0), (1, 1), (2, 2)];
-        let result = get_random_move(&valid_moves);
+        let result = get_random_move(&valid_moves, &mut rand::rng());
         assert!(valid_moves.contains(&result));
     }
 
@@ -194,16 +536,193 @@ mod tests {
     }
 
     #[test]
-    fn test_evaluate_board() {
+    fn test_get_best_move_uses_book() {
+        let game = crate::game::Game::new();
+        let config = CpuConfig { book: Some(Arc::new(Book::parse("f5d6").unwrap())), ..CpuConfig::default() };
+        for _ in 0..5 {
+            assert_eq!(get_best_move_with(&game, Disc::Black, CpuLevel::Easy, &config), (4, 5));
+        }
+    }
+
+    #[test]
+    fn test_seed_makes_random_moves_reproducible() {
+        let game = crate::game::Game::from_transcript("f5d6c3d3c4f4c5b3c2e6c6b4b5").unwrap();
+        let player = game.current_turn();
+        let config = CpuConfig { book: None, seed: Some(42), ..CpuConfig::default() };
+        let first = get_best_move_with(&game, player, CpuLevel::Easy, &config);
+        for _ in 0..10 {
+            assert_eq!(get_best_move_with(&game, player, CpuLevel::Easy, &config), first);
+        }
+    }
+
+    #[test]
+    fn test_search_respects_depth_limit() {
+        let game = crate::game::Game::new();
+        let result = search(&game, Disc::Black, &SearchLimits::depth(3)).unwrap();
+        assert_eq!(result.depth, 3);
+        assert!(!result.is_exact());
+        assert!(game.get_valid_moves(Disc::Black).contains(&result.best_move));
+        assert!(result.nodes > 0);
+    }
+
+    #[test]
+    fn test_search_respects_node_budget() {
+        let game = crate::game::Game::new();
+        let result = search(&game, Disc::Black, &SearchLimits::nodes(5_000)).unwrap();
+        assert!(result.depth >= 1);
+        assert!(result.depth < 60);
+        assert!(result.nodes <= 5_000 + 64);
+    }
+
+    #[test]
+    fn test_search_respects_time_budget() {
+        let game = crate::game::Game::new();
+        let result = search(&game, Disc::Black, &SearchLimits::time(Duration::from_millis(50))).unwrap();
+        assert!(result.elapsed < Duration::from_millis(500));
+        assert!(game.get_valid_moves(Disc::Black).contains(&result.best_move));
+    }
+
+    #[test]
+    fn test_search_without_moves() {
+        let game = crate::game::Game::new();
+        assert_eq!(search(&game, Disc::Empty, &SearchLimits::depth(2)), None);
+    }
+
+    #[test]
+    fn test_minimax_scores_finished_game_exactly() {
+        // Black to move: h1 captures the whole top row and leaves white without discs.
+        let mut board = Board::empty();
+        board.put_disc(0, 0, Disc::Black);
+        for c in 1..7 {
+            board.put_disc(0, c, Disc::White);
+        }
+        let mut table = TranspositionTable::new(1);
+        let weights = Weights::builtin();
+        let mut searcher = Searcher {
+            deadline: None,
+            node_limit: None,
+            nodes: 0,
+            aborted: false,
+            table: &mut table,
+            evaluator: weights.as_ref(),
+        };
+        let score = searcher.minimax(&board, Disc::Black, 3, -INFINITY, INFINITY, false, false);
+        assert_eq!(score, 64 * WIN_SCALE);
+    }
+
+    // Plain negamax without pruning or hashing, as a reference for the real search.
+    fn reference_negamax(board: &Board, player: Disc, depth: u32, passed: bool) -> i32 {
+        let moves = board.legal_moves(player);
+        if moves == 0 {
+            if passed {
+                return final_score(board, player);
+            }
+            return -reference_negamax(board, player.opponent(), depth, true);
+        }
+        if depth == 0 {
+            return evaluate_board(board, player);
+        }
+        squares(moves)
+            .map(|(r, c)| {
+                let mut next = *board;
+                next.apply_move(r, c, player);
+                -reference_negamax(&next, player.opponent(), depth - 1, false)
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_search_with_table_matches_reference_score() {
+        let mut game = crate::game::Game::new();
+        for _ in 0..4 {
+            let mv = game.legal_moves()[0];
+            assert!(game.play(mv));
+        }
+        let player = game.current_turn();
+        let expected = reference_negamax(game.board(), player, 4, false);
+
+        let mut table = TranspositionTable::new(1);
+        let first = search_with_table(&game, player, &SearchLimits::depth(4), &*Weights::builtin(), &mut table).unwrap();
+        assert_eq!(first.score, expected);
+
+        // A second search reuses the table and must agree.
+        let second = search_with_table(&game, player, &SearchLimits::depth(4), &*Weights::builtin(), &mut table).unwrap();
+        assert_eq!(second.score, expected);
+        assert!(second.nodes <= first.nodes);
+    }
+
+    #[test]
+    fn test_search_solves_endgame_exactly() {
+        let mut game = crate::game::Game::new();
+        while game.board().count(Disc::Empty) > 10 {
+            let moves = game.legal_moves();
+            assert!(game.play(moves[game.history().len() % moves.len()]));
+        }
+        let player = game.current_turn();
+        let solution = endgame::solve(game.board(), player, SolveMode::Exact);
+
+        let result = search(&game, player, &SearchLimits::depth(1)).unwrap();
+        assert_eq!(result.score, solution.score * WIN_SCALE);
+        assert_eq!(result.depth, game.board().count(Disc::Empty));
+
+        let heuristic = SearchLimits { endgame_empties: 0, ..SearchLimits::depth(1) };
+        assert_eq!(search(&game, player, &heuristic).unwrap().depth, 1);
+
+        // A solve that runs out of budget falls back to the deepening search.
+        let budget = SearchLimits { endgame_empties: 10, ..SearchLimits::nodes(100) };
+        let result = search(&game, player, &budget).unwrap();
+        assert!(!result.is_exact());
+        assert!(game.get_valid_moves(player).contains(&result.best_move));
+        let timed = SearchLimits { endgame_empties: 10, ..SearchLimits::time(Duration::ZERO) };
+        assert!(!search(&game, player, &timed).unwrap().is_exact());
+    }
+
+    #[test]
+    fn test_proven_draw_is_exact() {
+        // Black's a1 flips b1 and c1 and fills the board at 32-32.
+        let position = format!("-OO{}{} X", "X".repeat(29), "O".repeat(32));
+        let game = crate::game::Game::from_position_str(&position).unwrap();
+        let mut table = TranspositionTable::new(1);
+        let solved = search_with_table(&game, Disc::Black, &SearchLimits::depth(1), &*Weights::builtin(), &mut table);
+        let searched = SearchLimits { endgame_empties: 0, ..SearchLimits::depth(1) };
+        let searched = search_with_table(&game, Disc::Black, &searched, &*Weights::builtin(), &mut table);
+        let ranked = rank_moves(&game, &SearchLimits::depth(1), &*Weights::builtin(), &mut table);
+        for result in [solved.unwrap(), searched.unwrap(), ranked[0]] {
+            assert_eq!((result.best_move, result.score), ((0, 0), 0));
+            assert!(result.is_exact());
+            assert_eq!(result.disc_margin(), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_large_evaluations_are_not_proven_results() {
+        // A disc is worth more than a won game to these weights.
+        let weights = Weights::parse("phase 60\ndiscs 1000\n").unwrap();
         let game = crate::game::Game::new();
-        let score = evaluate_board(&game, Disc::Black);
-        assert!(score >= i32::MIN && score <= i32::MAX);
+        let mut table = TranspositionTable::new(1);
+        let result = search_with_table(&game, Disc::Black, &SearchLimits::depth(3), &weights, &mut table).unwrap();
+        assert_eq!(result.depth, 3);
+        assert_eq!(result.score, WIN_SCALE - 1);
+        assert!(!result.is_exact());
     }
 
     #[test]
-    fn test_calculate_positional_score() {
+    fn test_rank_moves_agrees_with_search() {
+        let game = crate::game::Game::from_transcript("f5d6c3d3c4").unwrap();
+        let limits = SearchLimits::depth(4);
+        let ranked = rank_moves(&game, &limits, &*Weights::builtin(), &mut TranspositionTable::new(1));
+        assert_eq!(ranked.len(), game.get_valid_moves(game.current_turn()).len());
+        assert!(ranked.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        let best = search(&game, game.current_turn(), &limits).unwrap();
+        assert_eq!(ranked[0].score, best.score);
+    }
+
+    #[test]
+    fn test_evaluate_board() {
         let game = crate::game::Game::new();
-        let score = calculate_positional_score(&game, Disc::Black);
-        assert!(score >= i32::MIN && score <= i32::MAX);
+        let score = evaluate_board(game.board(), Disc::Black);
+        assert_eq!(score, 0);
     }
 }
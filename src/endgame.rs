@@ -0,0 +1,254 @@
+use crate::board::{squares, Board, Disc};
+use crate::game::GameResult;
+use std::time::Instant;
+
+/// Empty-square count at or below which the Hard CPU switches to the solver.
+pub const DEFAULT_ENDGAME_EMPTIES: u32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveMode {
+    /// Only determine whether the side to move wins, draws or loses. Much faster
+    /// than an exact solve; the score is -1, 0 or 1.
+    WinLossDraw,
+    /// Determine the exact final disc margin under perfect play.
+    Exact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Solution {
+    /// Best move for the side to move, or `None` if it has to pass or the game is over.
+    pub best_move: Option<(usize, usize)>,
+    /// Final margin for the side to move (empties go to the winner), or its sign
+    /// in [`SolveMode::WinLossDraw`].
+    pub score: i32,
+    pub nodes: u64,
+}
+
+/// Solves the position with `player` to move by searching every line to the end of the game.
+pub fn solve(board: &Board, player: Disc, mode: SolveMode) -> Solution {
+    solve_within(board, player, mode, None, None).expect("an unlimited solve always finishes")
+}
+
+/// Like [`solve`], but gives up once `deadline` passes or `node_limit` positions
+/// have been visited. A solve that gives up returns the number of positions it
+/// visited as the error.
+pub fn solve_within(
+    board: &Board,
+    player: Disc,
+    mode: SolveMode,
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+) -> Result<Solution, u64> {
+    let (alpha, beta) = match mode {
+        SolveMode::WinLossDraw => (-1, 1),
+        SolveMode::Exact => (-64, 64),
+    };
+    let mut solver = Solver { nodes: 0, deadline, node_limit, aborted: false };
+    let moves = board.legal_moves(player);
+
+    if moves == 0 {
+        let score = solver.negamax(board, player, alpha, beta, false);
+        if solver.aborted {
+            return Err(solver.nodes);
+        }
+        return Ok(Solution { best_move: None, score: clamp(score, mode), nodes: solver.nodes });
+    }
+
+    let mut alpha = alpha;
+    let mut best = (None, -65);
+    for (r, c) in solver.ordered_moves(board, player, moves) {
+        let mut next = *board;
+        next.apply_move(r, c, player);
+        let score = -solver.negamax(&next, player.opponent(), -beta, -alpha, false);
+        if solver.aborted {
+            return Err(solver.nodes);
+        }
+        if score > best.1 {
+            best = (Some((r, c)), score);
+        }
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    Ok(Solution { best_move: best.0, score: clamp(best.1, mode), nodes: solver.nodes })
+}
+
+fn clamp(score: i32, mode: SolveMode) -> i32 {
+    match mode {
+        SolveMode::WinLossDraw => score.signum(),
+        SolveMode::Exact => score,
+    }
+}
+
+fn final_margin(board: &Board, player: Disc) -> i32 {
+    GameResult::from_counts(board.count(Disc::Black), board.count(Disc::White)).margin(player)
+}
+
+struct Solver {
+    nodes: u64,
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    aborted: bool,
+}
+
+impl Solver {
+    fn out_of_budget(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        if self.node_limit.is_some_and(|limit| self.nodes >= limit) {
+            self.aborted = true;
+        }
+        if self.nodes.is_multiple_of(1024) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    // Returns 0 once the budget runs out; callers then discard the result.
+    fn negamax(&mut self, board: &Board, player: Disc, alpha: i32, beta: i32, passed: bool) -> i32 {
+        if self.out_of_budget() {
+            return 0;
+        }
+        self.nodes += 1;
+        let moves = board.legal_moves(player);
+        if moves == 0 {
+            if passed {
+                return final_margin(board, player);
+            }
+            return -self.negamax(board, player.opponent(), -beta, -alpha, true);
+        }
+
+        let mut alpha = alpha;
+        let mut best = -65;
+        for (r, c) in self.ordered_moves(board, player, moves) {
+            let mut next = *board;
+            next.apply_move(r, c, player);
+            let score = -self.negamax(&next, player.opponent(), -beta, -alpha, false);
+            if self.aborted {
+                return 0;
+            }
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+
+    // Fastest-first ordering: moves that leave the opponent fewer replies are tried
+    // first. Not worth the cost in the last few plies.
+    fn ordered_moves(&self, board: &Board, player: Disc, moves: u64) -> Vec<(usize, usize)> {
+        let mut ordered: Vec<(usize, usize)> = squares(moves).collect();
+        if board.count(Disc::Empty) > 6 {
+            ordered.sort_by_cached_key(|&(r, c)| {
+                let mut next = *board;
+                next.apply_move(r, c, player);
+                next.legal_moves(player.opponent()).count_ones()
+            });
+        }
+        ordered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    // Plays the first valid move (or passes) until `empties` squares are left.
+    fn position_with_empties(empties: u32) -> Game {
+        let mut game = Game::new();
+        while game.board().count(Disc::Empty) > empties {
+            let moves = game.legal_moves();
+            let index = game.history().len() % moves.len();
+            assert!(game.play(moves[index]));
+        }
+        game
+    }
+
+    fn brute_force(board: &Board, player: Disc, passed: bool) -> i32 {
+        let moves = board.legal_moves(player);
+        if moves == 0 {
+            if passed {
+                return final_margin(board, player);
+            }
+            return -brute_force(board, player.opponent(), true);
+        }
+        squares(moves)
+            .map(|(r, c)| {
+                let mut next = *board;
+                next.apply_move(r, c, player);
+                -brute_force(&next, player.opponent(), false)
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_exact_solve_matches_brute_force() {
+        for empties in [4, 6, 8] {
+            let game = position_with_empties(empties);
+            let player = game.current_turn();
+            let expected = brute_force(game.board(), player, false);
+            let solution = solve(game.board(), player, SolveMode::Exact);
+            assert_eq!(solution.score, expected, "{} empties", empties);
+
+            let mut after = *game.board();
+            if let Some((r, c)) = solution.best_move {
+                after.apply_move(r, c, player);
+                assert_eq!(-brute_force(&after, player.opponent(), false), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_win_loss_draw_matches_exact_sign() {
+        for empties in [5, 7, 9] {
+            let game = position_with_empties(empties);
+            let player = game.current_turn();
+            let exact = solve(game.board(), player, SolveMode::Exact);
+            let wld = solve(game.board(), player, SolveMode::WinLossDraw);
+            assert_eq!(wld.score, exact.score.signum());
+            assert!(wld.nodes <= exact.nodes);
+        }
+    }
+
+    #[test]
+    fn test_solve_within_gives_up_when_out_of_budget() {
+        let game = position_with_empties(12);
+        let player = game.current_turn();
+        let solution = solve(game.board(), player, SolveMode::Exact);
+        assert_eq!(solve_within(game.board(), player, SolveMode::Exact, None, Some(100)), Err(100));
+        let limit = Some(solution.nodes);
+        assert_eq!(solve_within(game.board(), player, SolveMode::Exact, None, limit), Ok(solution));
+        let past = Some(Instant::now());
+        assert!(solve_within(game.board(), player, SolveMode::Exact, past, None).is_err());
+    }
+
+    #[test]
+    fn test_solve_finished_game() {
+        let mut board = Board::empty();
+        board.put_disc(0, 0, Disc::Black);
+        board.put_disc(0, 1, Disc::Black);
+        board.put_disc(7, 7, Disc::White);
+        let solution = solve(&board, Disc::White, SolveMode::Exact);
+        assert_eq!(solution.best_move, None);
+        assert_eq!(solution.score, -62);
+    }
+
+    #[test]
+    fn test_solve_prefers_larger_win() {
+        // Black to move: h1 captures the whole top row and ends the game 64-0.
+        let mut board = Board::empty();
+        board.put_disc(0, 0, Disc::Black);
+        for c in 1..7 {
+            board.put_disc(0, c, Disc::White);
+        }
+        let solution = solve(&board, Disc::Black, SolveMode::Exact);
+        assert_eq!(solution.best_move, Some((0, 7)));
+        assert_eq!(solution.score, 64);
+    }
+}
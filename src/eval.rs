@@ -0,0 +1,241 @@
+use crate::board::{neighbours, stable_discs, Board, Disc};
+use crate::pattern::{self, PatternWeights};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+const DEFAULT_WEIGHTS: &str = include_str!("../weights/default.txt");
+
+/// Static evaluation used at the leaves of the Hard search.
+///
+/// Scores are from the point of view of `player`, the side to move, on roughly the
+/// scale of ten points per disc of final margin.
+pub trait Evaluator: Send + Sync + fmt::Debug {
+    fn evaluate(&self, board: &Board, player: Disc) -> i32;
+}
+
+/// Feature weights for one game phase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseWeights {
+    /// The phase covers positions with at most this many empty squares.
+    pub max_empties: u32,
+    pub discs: i32,
+    pub mobility: i32,
+    pub potential_mobility: i32,
+    pub frontier: i32,
+    pub stability: i32,
+    pub parity: i32,
+    /// Value of a disc on each square, indexed `row * 8 + col`.
+    pub squares: [i32; 64],
+}
+
+impl PhaseWeights {
+    fn new(max_empties: u32) -> Self {
+        PhaseWeights {
+            max_empties,
+            discs: 0,
+            mobility: 0,
+            potential_mobility: 0,
+            frontier: 0,
+            stability: 0,
+            parity: 0,
+            squares: [0; 64],
+        }
+    }
+
+    fn evaluate(&self, board: &Board, player: Disc) -> i32 {
+        let own = board.bitboard(player);
+        let opponent = board.bitboard(player.opponent());
+        let empty = board.bitboard(Disc::Empty);
+        let diff = |mine: u64, theirs: u64| mine.count_ones() as i32 - theirs.count_ones() as i32;
+
+        let mut score = self.discs * diff(own, opponent);
+        if self.mobility != 0 {
+            score += self.mobility * diff(board.legal_moves(player), board.legal_moves(player.opponent()));
+        }
+        if self.potential_mobility != 0 {
+            score += self.potential_mobility * diff(neighbours(opponent) & empty, neighbours(own) & empty);
+        }
+        if self.frontier != 0 {
+            let frontier = neighbours(empty);
+            score += self.frontier * diff(own & frontier, opponent & frontier);
+        }
+        if self.stability != 0 {
+            score += self.stability * diff(stable_discs(own, opponent), stable_discs(opponent, own));
+        }
+        if self.parity != 0 {
+            score += if empty.count_ones() % 2 == 1 { self.parity } else { -self.parity };
+        }
+        for (index, value) in self.squares.iter().enumerate() {
+            let bit = 1u64 << index;
+            if own & bit != 0 {
+                score += value;
+            } else if opponent & bit != 0 {
+                score -= value;
+            }
+        }
+        score
+    }
+}
+
+/// A weighted sum of classic Othello features, with separate weights per phase.
+///
+/// Weights files hold `phase EMPTIES` sections, each followed by `NAME VALUE`
+/// lines for the features and `squares` with 64 values from a1 to h8; `#` starts
+/// a comment. See `weights/default.txt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Weights {
+    // Sorted by `max_empties`, smallest first.
+    phases: Vec<PhaseWeights>,
+}
+
+impl Weights {
+    /// Returns `None` if `phases` is empty.
+    pub fn new(mut phases: Vec<PhaseWeights>) -> Option<Self> {
+        if phases.is_empty() {
+            return None;
+        }
+        phases.sort_by_key(|phase| phase.max_empties);
+        Some(Weights { phases })
+    }
+
+    /// The weights shipped with the program (`weights/default.txt`).
+    pub fn builtin() -> Arc<Weights> {
+        static BUILTIN: OnceLock<Arc<Weights>> = OnceLock::new();
+        BUILTIN
+            .get_or_init(|| Arc::new(Weights::parse(DEFAULT_WEIGHTS).expect("built-in weights are valid")))
+            .clone()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Weights> {
+        Weights::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Weights> {
+        let mut tokens = text
+            .lines()
+            .enumerate()
+            .flat_map(|(number, line)| {
+                line.split('#').next().unwrap().split_whitespace().map(move |token| (number + 1, token))
+            });
+        let mut phases: Vec<PhaseWeights> = Vec::new();
+        while let Some((number, name)) = tokens.next() {
+            let mut value = || -> io::Result<i32> {
+                let (number, text) =
+                    tokens.next().ok_or_else(|| invalid_data(format!("line {}: {} needs a value", number, name)))?;
+                text.parse().map_err(|_| invalid_data(format!("line {}: invalid value '{}'", number, text)))
+            };
+            if name == "phase" {
+                let empties = value()?;
+                if !(0..=64).contains(&empties) {
+                    return Err(invalid_data(format!("line {}: phase must be 0 to 64 empties", number)));
+                }
+                phases.push(PhaseWeights::new(empties as u32));
+                continue;
+            }
+            let Some(phase) = phases.last_mut() else {
+                return Err(invalid_data(format!("line {}: '{}' comes before the first phase", number, name)));
+            };
+            match name {
+                "discs" => phase.discs = value()?,
+                "mobility" => phase.mobility = value()?,
+                "potential_mobility" => phase.potential_mobility = value()?,
+                "frontier" => phase.frontier = value()?,
+                "stability" => phase.stability = value()?,
+                "parity" => phase.parity = value()?,
+                "squares" => {
+                    for square in 0..64 {
+                        phase.squares[square] = value()?;
+                    }
+                }
+                _ => return Err(invalid_data(format!("line {}: unknown weight '{}'", number, name))),
+            }
+        }
+        Weights::new(phases).ok_or_else(|| invalid_data("no phases defined".to_string()))
+    }
+
+    /// The weights for a position with `empties` empty squares: the smallest phase
+    /// that covers it, or the last phase if none does.
+    pub fn phase(&self, empties: u32) -> &PhaseWeights {
+        self.phases
+            .iter()
+            .find(|phase| phase.max_empties >= empties)
+            .unwrap_or_else(|| self.phases.last().unwrap())
+    }
+}
+
+impl Evaluator for Weights {
+    fn evaluate(&self, board: &Board, player: Disc) -> i32 {
+        self.phase(board.count(Disc::Empty)).evaluate(board, player)
+    }
+}
+
+/// Loads either kind of weights file: pattern weights written by training (see
+/// [`crate::pattern`]) or feature [`Weights`].
+pub fn load(path: impl AsRef<Path>) -> io::Result<Arc<dyn Evaluator>> {
+    let text = fs::read_to_string(path)?;
+    let first = text.lines().map(|line| line.split('#').next().unwrap().trim()).find(|line| !line.is_empty());
+    if first == Some(pattern::HEADER) {
+        Ok(Arc::new(PatternWeights::parse(&text)?))
+    } else {
+        Ok(Arc::new(Weights::parse(&text)?))
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn test_builtin_weights() {
+        let weights = Weights::builtin();
+        let phase = weights.phase(60);
+        assert_eq!((phase.discs, phase.mobility), (1, 5));
+        assert_eq!(phase.squares[0], 100);
+        assert_eq!(phase.squares[9], -50);
+        // The initial position is symmetric.
+        assert_eq!(weights.evaluate(&Board::new(), Disc::Black), 0);
+    }
+
+    #[test]
+    fn test_phases() {
+        let weights = Weights::parse("phase 20\nparity 7\nphase 60 # the rest\ndiscs 1\n").unwrap();
+        assert_eq!(weights.phase(12).parity, 7);
+        assert_eq!(weights.phase(20).parity, 7);
+        assert_eq!(weights.phase(21).discs, 1);
+        assert_eq!(weights.phase(64).discs, 1);
+
+        // Odd empties favour the side to move.
+        let game = Game::from_transcript("f5").unwrap();
+        assert_eq!(Weights::parse("phase 64 parity 3").unwrap().evaluate(game.board(), Disc::White), 3);
+    }
+
+    #[test]
+    fn test_features() {
+        // After f5, Black has four discs to White's one; White has three moves to Black's three.
+        let game = Game::from_transcript("f5").unwrap();
+        let board = game.board();
+        let score = |text: &str| Weights::parse(text).unwrap().evaluate(board, Disc::White);
+        assert_eq!(score("phase 60 discs 1"), -3);
+        assert_eq!(score("phase 60 mobility 1"), 0);
+        assert_eq!(score("phase 60 stability 1"), 0);
+        assert!(score("phase 60 frontier -1") > 0);
+        assert!(score("phase 60 potential_mobility 1") > 0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Weights::parse("").is_err());
+        assert!(Weights::parse("discs 1").is_err());
+        assert!(Weights::parse("phase 60\nsquares 1 2 3").is_err());
+        let error = Weights::parse("phase 60\ncorners 5").unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+    }
+}
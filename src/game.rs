@@ -1,19 +1,118 @@
-use crate::board::{Board, Disc};
+use crate::board::{square_bit, squares, Board, Disc};
+use crate::notation::{
+    format_position, format_square, parse_position, parse_square, tokenize, PositionError, TranscriptError,
+};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Move {
+    /// Place a disc on (row, col).
+    Place(usize, usize),
+    Pass,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Move::Place(row, col) => write!(f, "{}", format_square(*row, *col)),
+            Move::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = ();
+
+    /// Parses "f5" or a pass ("pass", "pa" or "--").
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.to_ascii_lowercase().as_str() {
+            "pass" | "pa" | "--" => Ok(Move::Pass),
+            square => parse_square(square).map(|(row, col)| Move::Place(row, col)).ok_or(()),
+        }
+    }
+}
+
+/// One entry in the move history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveRecord {
+    pub player: Disc,
+    pub mv: Move,
+    /// Mask of the opponent discs flipped by this move.
+    pub flipped: u64,
+}
+
+/// Final outcome of a game. Empty squares left at the end go to the winner
+/// (split evenly on a draw), following the World Othello Federation rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameResult {
+    pub winner: Option<Disc>,
+    pub black: u32,
+    pub white: u32,
+}
+
+impl GameResult {
+    pub fn from_counts(black: u32, white: u32) -> Self {
+        let empties = 64 - black - white;
+        if black > white {
+            GameResult { winner: Some(Disc::Black), black: black + empties, white }
+        } else if white > black {
+            GameResult { winner: Some(Disc::White), black, white: white + empties }
+        } else {
+            GameResult { winner: None, black: black + empties / 2, white: white + empties / 2 }
+        }
+    }
+
+    /// Final score of `player` minus that of the opponent.
+    pub fn margin(&self, player: Disc) -> i32 {
+        let diff = self.black as i32 - self.white as i32;
+        if player == Disc::White { -diff } else { diff }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Game {
-    pub board: Board,
-    pub current_turn: Disc,
+    board: Board,
+    current_turn: Disc,
+    start: (Board, Disc),
+    history: Vec<MoveRecord>,
+    redo_stack: Vec<MoveRecord>,
 }
 
 impl Game {
     pub fn new() -> Self {
+        Game::from_position(Board::new(), Disc::Black)
+    }
+
+    /// Starts a game from an arbitrary position with `side_to_move` to play.
+    pub fn from_position(board: Board, side_to_move: Disc) -> Self {
         Game {
-            board: Board::new(),
-            current_turn: Disc::Black,
+            board,
+            current_turn: side_to_move,
+            start: (board, side_to_move),
+            history: Vec::new(),
+            redo_stack: Vec::new(),
         }
     }
 
+    /// Starts a game from a position string such as
+    /// `---------------------------OX------XO--------------------------- X`.
+    /// See [`parse_position`] for the format.
+    pub fn from_position_str(text: &str) -> Result<Self, PositionError> {
+        let (board, side_to_move) = parse_position(text)?;
+        Ok(Game::from_position(board, side_to_move))
+    }
+
+    /// The current position in the format read by [`Game::from_position_str`].
+    pub fn position_string(&self) -> String {
+        format_position(&self.board, self.current_turn)
+    }
+
+    /// The position the game started from, before any move in the history.
+    pub fn start_position(&self) -> (Board, Disc) {
+        self.start
+    }
+
     pub fn current_turn(&self) -> Disc {
         self.current_turn
     }
@@ -23,110 +122,172 @@ impl Game {
     }
 
     pub fn is_valid_move(&self, row: usize, col: usize, player: Disc) -> bool {
-        if self.board.get_disc(row, col) != Some(Disc::Empty) {
+        row < 8 && col < 8 && self.board.legal_moves(player) & square_bit(row, col) != 0
+    }
+
+    pub fn get_valid_moves(&self, player: Disc) -> Vec<(usize, usize)> {
+        squares(self.board.legal_moves(player)).collect()
+    }
+
+    pub fn has_valid_move(&self, player: Disc) -> bool {
+        self.board.legal_moves(player) != 0
+    }
+
+    /// Places a disc for `player`, who must be the side to move. Returns `false`
+    /// if it is the other side's turn or the move is not valid.
+    pub fn make_move(&mut self, row: usize, col: usize, player: Disc) -> bool {
+        if player != self.current_turn {
+            return false;
+        }
+        let flipped = self.board.apply_move(row, col, player);
+        if flipped == 0 {
             return false;
         }
 
-        let opponent = if player == Disc::Black { Disc::White } else { Disc::Black };
-        let directions = [
-            (-1, -1), (-1, 0), (-1, 1),
-            (0, -1),          (0, 1),
-            (1, -1), (1, 0), (1, 1),
-        ];
-
-        for (dr, dc) in directions.iter() {
-            let mut r = row as i32 + dr;
-            let mut c = col as i32 + dc;
-            let mut has_opponent_disc = false;
-
-            while r >= 0 && r < 8 && c >= 0 && c < 8 {
-                match self.board.get_disc(r as usize, c as usize) {
-                    Some(d) if d == opponent => has_opponent_disc = true,
-                    Some(d) if d == player => {
-                        if has_opponent_disc {
-                            return true;
-                        }
-                        break;
-                    }
-                    _ => break,
-                }
-                r += dr;
-                c += dc;
-            }
+        self.current_turn = player.opponent();
+        self.history.push(MoveRecord { player, mv: Move::Place(row, col), flipped });
+        self.redo_stack.clear();
+        true
+    }
+
+    /// True when the side to move has no valid move but the game is not over.
+    pub fn must_pass(&self) -> bool {
+        !self.has_valid_move(self.current_turn) && self.has_valid_move(self.current_turn.opponent())
+    }
+
+    /// Moves available to the side to move: the valid placements, `[Move::Pass]`
+    /// when a pass is forced, or nothing once the game is over.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.must_pass() {
+            return vec![Move::Pass];
         }
-        false
+        squares(self.board.legal_moves(self.current_turn))
+            .map(|(row, col)| Move::Place(row, col))
+            .collect()
     }
 
-    pub fn get_valid_moves(&self, player: Disc) -> Vec<(usize, usize)> {
-        let mut valid_moves = Vec::new();
-        for r in 0..8 {
-            for c in 0..8 {
-                if self.is_valid_move(r, c, player) {
-                    valid_moves.push((r, c));
-                }
-            }
+    /// Plays `mv` for the side to move. Returns `false` if it is not legal.
+    pub fn play(&mut self, mv: Move) -> bool {
+        match mv {
+            Move::Place(row, col) => self.make_move(row, col, self.current_turn),
+            Move::Pass => self.pass(),
         }
-        valid_moves
     }
 
-    pub fn make_move(&mut self, row: usize, col: usize, player: Disc) -> bool {
-        if !self.is_valid_move(row, col, player) {
+    /// Passes the turn. Only allowed when the pass is forced.
+    pub fn pass(&mut self) -> bool {
+        if !self.must_pass() {
             return false;
         }
 
-        self.board.put_disc(row, col, player);
-        self.flip_discs(row, col, player);
-        self.current_turn = if self.current_turn == Disc::Black { Disc::White } else { Disc::Black };
+        let player = self.current_turn;
+        self.current_turn = player.opponent();
+        self.history.push(MoveRecord { player, mv: Move::Pass, flipped: 0 });
+        self.redo_stack.clear();
         true
     }
 
-    fn flip_discs(&mut self, row: usize, col: usize, player: Disc) {
-        let opponent = if player == Disc::Black { Disc::White } else { Disc::Black };
-        let directions = [
-            (-1, -1), (-1, 0), (-1, 1),
-            (0, -1),          (0, 1),
-            (1, -1), (1, 0), (1, 1),
-        ];
-
-        for (dr, dc) in directions.iter() {
-            let mut r = row as i32 + dr;
-            let mut c = col as i32 + dc;
-            let mut discs_to_flip = Vec::new();
-
-            while r >= 0 && r < 8 && c >= 0 && c < 8 {
-                match self.board.get_disc(r as usize, c as usize) {
-                    Some(d) if d == opponent => discs_to_flip.push((r as usize, c as usize)),
-                    Some(d) if d == player => {
-                        for (fr, fc) in &discs_to_flip {
-                            self.board.put_disc(*fr, *fc, player);
-                        }
-                        break;
-                    }
-                    _ => break,
-                }
-                r += dr;
-                c += dc;
+    /// Replays a transcript such as "f5d6c3d3c4" from the standard starting position.
+    pub fn from_transcript(transcript: &str) -> Result<Game, TranscriptError> {
+        let mut game = Game::new();
+        game.play_transcript(transcript)?;
+        Ok(game)
+    }
+
+    /// Plays the moves of a transcript from the current position. Forced passes may
+    /// be left out or written as "pa"/"--". On error the game is left unchanged.
+    pub fn play_transcript(&mut self, transcript: &str) -> Result<(), TranscriptError> {
+        let mut game = self.clone();
+        for (index, token) in tokenize(transcript).into_iter().enumerate() {
+            let ply = index + 1;
+            let mv: Move = match token.parse() {
+                Ok(mv) => mv,
+                Err(()) => return Err(TranscriptError::Malformed { ply, text: token }),
+            };
+            if mv != Move::Pass && game.must_pass() {
+                game.play(Move::Pass);
+            }
+            if !game.play(mv) {
+                return Err(TranscriptError::IllegalMove { ply, text: token.to_ascii_lowercase() });
+            }
+        }
+        *self = game;
+        Ok(())
+    }
+
+    /// The moves played so far in standard notation, e.g. "f5d6c3". Passes are left out.
+    pub fn transcript(&self) -> String {
+        self.history
+            .iter()
+            .filter(|record| record.mv != Move::Pass)
+            .map(|record| record.mv.to_string())
+            .collect()
+    }
+
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Takes back the last move (or pass). Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(record) = self.history.pop() else {
+            return false;
+        };
+
+        if let Move::Place(row, col) = record.mv {
+            self.board.put_disc(row, col, Disc::Empty);
+            for (r, c) in squares(record.flipped) {
+                self.board.put_disc(r, c, record.player.opponent());
             }
         }
+        self.current_turn = record.player;
+        self.redo_stack.push(record);
+        true
+    }
+
+    /// Replays the last undone move. Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(record) = self.redo_stack.pop() else {
+            return false;
+        };
+
+        if let Move::Place(row, col) = record.mv {
+            self.board.apply_move(row, col, record.player);
+        }
+        self.current_turn = record.player.opponent();
+        self.history.push(record);
+        true
     }
 
     pub fn is_game_over(&self) -> bool {
-        self.get_valid_moves(Disc::Black).is_empty() && self.get_valid_moves(Disc::White).is_empty()
+        !self.has_valid_move(Disc::Black) && !self.has_valid_move(Disc::White)
     }
 
     pub fn count_discs(&self) -> (u32, u32) {
-        let mut black_count = 0;
-        let mut white_count = 0;
-        for r in 0..8 {
-            for c in 0..8 {
-                match self.board.get_disc(r, c) {
-                    Some(Disc::Black) => black_count += 1,
-                    Some(Disc::White) => white_count += 1,
-                    _ => (),
-                }
-            }
+        (self.board.count(Disc::Black), self.board.count(Disc::White))
+    }
+
+    /// The final result, or `None` while the game is still in progress.
+    pub fn result(&self) -> Option<GameResult> {
+        if !self.is_game_over() {
+            return None;
         }
-        (black_count, white_count)
+        let (black, white) = self.count_discs();
+        Some(GameResult::from_counts(black, white))
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -179,6 +340,17 @@ mod tests {
         assert!(!game.make_move(0, 0, Disc::White));
     }
 
+    #[test]
+    fn test_make_move_rejects_wrong_side() {
+        let mut game = Game::new();
+        // f4 is legal for White, but Black is to move.
+        assert!(game.is_valid_move(3, 5, Disc::White));
+        assert!(!game.make_move(3, 5, Disc::White));
+        assert_eq!(*game.board(), Board::new());
+        assert_eq!(game.current_turn(), Disc::Black);
+        assert!(game.history().is_empty());
+    }
+
     #[test]
     fn test_count_discs() {
         let game = Game::new();
@@ -187,9 +359,227 @@ mod tests {
         assert_eq!(white_count, 2);
     }
 
+    #[test]
+    fn test_history_records_moves_and_flips() {
+        let mut game = Game::new();
+        game.make_move(2, 3, Disc::Black);
+        game.make_move(2, 2, Disc::White);
+
+        let history = game.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].player, Disc::Black);
+        assert_eq!(history[0].mv, Move::Place(2, 3));
+        assert_eq!(history[0].flipped, square_bit(3, 3));
+        assert_eq!(history[1].player, Disc::White);
+        assert_eq!(history[1].flipped, square_bit(3, 3));
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::new();
+        assert!(!game.undo());
+        game.make_move(2, 3, Disc::Black);
+        game.make_move(2, 2, Disc::White);
+        let after_two = *game.board();
+
+        assert!(game.undo());
+        assert_eq!(game.current_turn(), Disc::White);
+        assert_eq!(game.board().get_disc(2, 2), Some(Disc::Empty));
+        assert_eq!(game.board().get_disc(3, 3), Some(Disc::Black));
+
+        assert!(game.undo());
+        assert_eq!(*game.board(), Board::new());
+        assert_eq!(game.current_turn(), Disc::Black);
+        assert!(!game.can_undo());
+
+        assert!(game.redo());
+        assert!(game.redo());
+        assert!(!game.redo());
+        assert_eq!(*game.board(), after_two);
+        assert_eq!(game.current_turn(), Disc::Black);
+        assert_eq!(game.history().len(), 2);
+    }
+
+    #[test]
+    fn test_new_move_clears_redo() {
+        let mut game = Game::new();
+        game.make_move(2, 3, Disc::Black);
+        game.undo();
+        assert!(game.can_redo());
+        game.make_move(3, 2, Disc::Black);
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn test_pass_only_when_no_moves() {
+        let mut game = Game::new();
+        assert!(!game.pass());
+
+        // Black's only disc sits in the corner, so white cannot outflank it.
+        let mut board = Board::empty();
+        board.put_disc(0, 0, Disc::Black);
+        board.put_disc(0, 1, Disc::White);
+        board.put_disc(7, 7, Disc::White);
+        let mut game = Game::from_position(board, Disc::White);
+        assert!(game.pass());
+        assert_eq!(game.current_turn(), Disc::Black);
+        assert_eq!(game.history()[0].mv, Move::Pass);
+
+        assert!(game.undo());
+        assert_eq!(game.current_turn(), Disc::White);
+    }
+
     #[test]
     fn test_is_game_over() {
         let game = Game::new();
         assert!(!game.is_game_over());
+        assert_eq!(game.result(), None);
+    }
+
+    #[test]
+    fn test_legal_moves_and_play() {
+        let mut game = Game::new();
+        let moves = game.legal_moves();
+        assert_eq!(moves.len(), 4);
+        assert!(moves.contains(&Move::Place(2, 3)));
+        assert!(!game.play(Move::Pass));
+        assert!(!game.play(Move::Place(0, 0)));
+        assert!(game.play(Move::Place(2, 3)));
+        assert_eq!(game.current_turn(), Disc::White);
+    }
+
+    #[test]
+    fn test_move_notation() {
+        assert_eq!(Move::Place(4, 5).to_string(), "f5");
+        assert_eq!(Move::Pass.to_string(), "pass");
+        assert_eq!("F5".parse(), Ok(Move::Place(4, 5)));
+        assert_eq!("pa".parse(), Ok(Move::Pass));
+        assert_eq!("j9".parse::<Move>(), Err(()));
+    }
+
+    #[test]
+    fn test_transcript_round_trip() {
+        let game = Game::from_transcript("f5d6c3d3c4").unwrap();
+        assert_eq!(game.history().len(), 5);
+        assert_eq!(game.current_turn(), Disc::White);
+        assert_eq!(game.transcript(), "f5d6c3d3c4");
+
+        let spaced = Game::from_transcript("F5 d6 C3\nd3 c4").unwrap();
+        assert_eq!(*spaced.board(), *game.board());
+    }
+
+    #[test]
+    fn test_transcript_errors_point_at_move() {
+        assert_eq!(
+            Game::from_transcript("f5d6a1").unwrap_err(),
+            TranscriptError::IllegalMove { ply: 3, text: "a1".to_string() }
+        );
+        assert_eq!(
+            Game::from_transcript("f5x7").unwrap_err(),
+            TranscriptError::Malformed { ply: 2, text: "x7".to_string() }
+        );
+        assert_eq!(
+            Game::from_transcript("f5pa").unwrap_err(),
+            TranscriptError::IllegalMove { ply: 2, text: "pa".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_play_transcript_leaves_game_unchanged_on_error() {
+        let mut game = Game::from_transcript("f5").unwrap();
+        assert!(game.play_transcript("d6a1").is_err());
+        assert_eq!(game.transcript(), "f5");
+        game.play_transcript("d6").unwrap();
+        assert_eq!(game.transcript(), "f5d6");
+    }
+
+    #[test]
+    fn test_transcript_of_finished_game() {
+        // Shortest possible game: white is wiped out after nine moves.
+        let game = Game::from_transcript("f5d6c5f4e7f6g5e6e3").unwrap();
+        assert!(game.is_game_over());
+        assert_eq!(game.transcript(), "f5d6c5f4e7f6g5e6e3");
+    }
+
+    #[test]
+    fn test_position_string() {
+        let mut game = Game::from_position_str(
+            "---------------------------OX------XO--------------------------- X",
+        )
+        .unwrap();
+        assert_eq!(*game.board(), Board::new());
+        assert_eq!(game.current_turn(), Disc::Black);
+
+        game.play(Move::Place(4, 5));
+        assert_eq!(game.position_string(), "---------------------------OX------XXX-------------------------- O");
+        let copy = Game::from_position_str(&game.position_string()).unwrap();
+        assert_eq!(*copy.board(), *game.board());
+        assert_eq!(copy.current_turn(), Disc::White);
+        assert!(copy.history().is_empty());
+    }
+
+    #[test]
+    fn test_game_from_position_keeps_start() {
+        let mut board = Board::new();
+        board.apply_move(4, 5, Disc::Black);
+        let mut game = Game::from_position(board, Disc::White);
+        game.play_transcript("d6c3").unwrap();
+        assert_eq!(game.start_position(), (board, Disc::White));
+        assert_eq!(game.transcript(), "d6c3");
+        while game.undo() {}
+        assert_eq!(*game.board(), board);
+        assert_eq!(game.current_turn(), Disc::White);
+    }
+
+    #[test]
+    fn test_forced_pass_is_detected() {
+        let mut board = Board::empty();
+        board.put_disc(0, 0, Disc::Black);
+        board.put_disc(0, 1, Disc::White);
+        board.put_disc(7, 7, Disc::White);
+        let game = Game::from_position(board, Disc::White);
+        assert!(game.must_pass());
+        assert_eq!(game.legal_moves(), vec![Move::Pass]);
+    }
+
+    #[test]
+    fn test_no_pass_once_game_is_over() {
+        let mut board = Board::empty();
+        board.put_disc(0, 0, Disc::Black);
+        board.put_disc(7, 7, Disc::White);
+        let mut game = Game::from_position(board, Disc::Black);
+        assert!(game.is_game_over());
+        assert!(!game.must_pass());
+        assert!(game.legal_moves().is_empty());
+        assert!(!game.pass());
+    }
+
+    #[test]
+    fn test_game_result_awards_empties_to_winner() {
+        let result = GameResult::from_counts(30, 20);
+        assert_eq!(result.winner, Some(Disc::Black));
+        assert_eq!((result.black, result.white), (44, 20));
+        assert_eq!(result.margin(Disc::Black), 24);
+        assert_eq!(result.margin(Disc::White), -24);
+
+        let result = GameResult::from_counts(10, 40);
+        assert_eq!(result.winner, Some(Disc::White));
+        assert_eq!((result.black, result.white), (10, 54));
+
+        let result = GameResult::from_counts(29, 29);
+        assert_eq!(result.winner, None);
+        assert_eq!((result.black, result.white), (32, 32));
+    }
+
+    #[test]
+    fn test_result_of_finished_game() {
+        let mut board = Board::empty();
+        board.put_disc(0, 0, Disc::Black);
+        board.put_disc(0, 1, Disc::Black);
+        board.put_disc(7, 7, Disc::White);
+        let game = Game::from_position(board, Disc::White);
+        let result = game.result().unwrap();
+        assert_eq!(result.winner, Some(Disc::Black));
+        assert_eq!((result.black, result.white), (63, 1));
     }
 }
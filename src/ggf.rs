@@ -0,0 +1,369 @@
+//! GGF (Generic Game Format) records, as exported by online Othello servers and
+//! read by viewers such as NBoard.
+//!
+//! A game is a list of `NAME[value]` properties between `(;` and `;)`:
+//!
+//! ```text
+//! (;GM[Othello]PC[GGS/os]PB[alice]PW[bob]RB[2012.5]RW[1890.0]TI[05:00//02:00]TY[8]
+//! RE[+4.000]BO[8 ---...--- *]B[F5//1.20]W[D6/-0.50/3.04]...;)
+//! ```
+//!
+//! (shown on two lines here; a record is a single line). Moves may carry the
+//! mover's evaluation and the seconds spent after slashes. Files of games hold
+//! one record per line.
+
+use crate::arena::{GameOutcome, MoveNote};
+use crate::board::{Board, Disc};
+use crate::clock::{Clock, TimeControl};
+use crate::game::{Game, Move};
+use crate::notation::{format_position, parse_position};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// A player's name and, if known, rating.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GgfPlayer {
+    pub name: String,
+    pub rating: Option<f64>,
+}
+
+impl GgfPlayer {
+    pub fn new(name: &str) -> Self {
+        GgfPlayer { name: name.to_string(), rating: None }
+    }
+}
+
+/// One GGF game record. Properties this type does not know are ignored when
+/// reading.
+#[derive(Debug, Clone)]
+pub struct GgfGame {
+    /// Where the game was played (`PC`).
+    pub place: Option<String>,
+    /// When the game was played (`DT`), as written by the server.
+    pub date: Option<String>,
+    pub black: GgfPlayer,
+    pub white: GgfPlayer,
+    /// The time control (`TI`), e.g. `05:00//02:00`.
+    pub time_control: Option<String>,
+    /// The result (`RE`): Black's final disc margin, such as `+4.000`, followed by
+    /// `:r` for a resignation or `:t` for a loss on time.
+    pub result: Option<String>,
+    pub game: Game,
+    /// Annotations for the moves of `game.history()`, by index. Moves past the end
+    /// have none.
+    pub notes: Vec<MoveNote>,
+}
+
+impl GgfGame {
+    /// Wraps `game`, taking the result from the final position if it is over.
+    pub fn new(game: Game, black: &str, white: &str) -> Self {
+        let result = game.result().map(|result| format!("{:+.3}", result.margin(Disc::Black) as f64));
+        GgfGame {
+            place: None,
+            date: None,
+            black: GgfPlayer::new(black),
+            white: GgfPlayer::new(white),
+            time_control: None,
+            result,
+            game,
+            notes: Vec::new(),
+        }
+    }
+
+    /// A record of a headless game, with its move annotations. Forfeits are
+    /// written as resignations.
+    pub fn from_outcome(outcome: &GameOutcome, black: &str, white: &str) -> Self {
+        let mut ggf = GgfGame::new(outcome.game.clone(), black, white);
+        let margin = outcome.result.margin(Disc::Black) as f64;
+        let suffix = if outcome.forfeit.is_some() { ":r" } else { "" };
+        ggf.result = Some(format!("{:+.3}{}", margin, suffix));
+        ggf.notes = outcome.notes.clone();
+        ggf
+    }
+
+    /// Reads a single game. Passes may be written (`PA`) or left out where forced.
+    pub fn parse(text: &str) -> io::Result<GgfGame> {
+        let (ggf, rest) = parse_game(text.trim()).map_err(invalid_data)?;
+        if !rest.trim().is_empty() {
+            return Err(invalid_data("unexpected text after the game".to_string()));
+        }
+        Ok(ggf)
+    }
+
+    /// Black's final disc margin from `result`, if it has one.
+    pub fn black_margin(&self) -> Option<f64> {
+        self.result.as_deref()?.split(':').next()?.trim().parse().ok()
+    }
+}
+
+impl fmt::Display for GgfGame {
+    /// Writes the game as one record, with every pass written out.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(;GM[Othello]")?;
+        if let Some(place) = &self.place {
+            write!(f, "PC[{}]", escape(place))?;
+        }
+        if let Some(date) = &self.date {
+            write!(f, "DT[{}]", escape(date))?;
+        }
+        write!(f, "PB[{}]PW[{}]", escape(&self.black.name), escape(&self.white.name))?;
+        if let Some(rating) = self.black.rating {
+            write!(f, "RB[{:.2}]", rating)?;
+        }
+        if let Some(rating) = self.white.rating {
+            write!(f, "RW[{:.2}]", rating)?;
+        }
+        if let Some(time_control) = &self.time_control {
+            write!(f, "TI[{}]", escape(time_control))?;
+        }
+        write!(f, "TY[8]")?;
+        if let Some(result) = &self.result {
+            write!(f, "RE[{}]", escape(result))?;
+        }
+        let (board, side) = self.game.start_position();
+        write!(f, "BO[8 {}]", format_position(&board, side).replace('X', "*"))?;
+        for (index, record) in self.game.history().iter().enumerate() {
+            let note = self.notes.get(index).copied().unwrap_or_default();
+            let name = if record.player == Disc::Black { "B" } else { "W" };
+            write!(f, "{}[{}", name, format_move(record.mv))?;
+            if note.eval.is_some() || note.time.is_some() {
+                write!(f, "/")?;
+                if let Some(eval) = note.eval {
+                    write!(f, "{:.2}", eval)?;
+                }
+                if let Some(time) = note.time {
+                    write!(f, "/{:.2}", time.as_secs_f64())?;
+                }
+            }
+            write!(f, "]")?;
+        }
+        write!(f, ";)")
+    }
+}
+
+/// Reads every game in `text`. Anything outside the `(;` ... `;)` records, such
+/// as the game counts some servers write between them, is skipped.
+pub fn parse_all(text: &str) -> io::Result<Vec<GgfGame>> {
+    let mut games = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("(;") {
+        let number = games.len() + 1;
+        let (ggf, after) =
+            parse_game(&rest[start..]).map_err(|message| invalid_data(format!("game {}: {}", number, message)))?;
+        games.push(ggf);
+        rest = after;
+    }
+    Ok(games)
+}
+
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<GgfGame>> {
+    parse_all(&fs::read_to_string(path)?)
+}
+
+/// Writes `games` one record per line.
+pub fn save(path: impl AsRef<Path>, games: &[GgfGame]) -> io::Result<()> {
+    let text: String = games.iter().map(|ggf| format!("{}\n", ggf)).collect();
+    fs::write(path, text)
+}
+
+/// The `TI` property for a clock: `MM:SS/INCREMENT/` for a total time control,
+/// `None` for the others, which GGF cannot express.
+pub fn time_control(clock: &Clock) -> Option<String> {
+    let TimeControl::Total { time, increment } = clock.control() else {
+        return None;
+    };
+    let format = |duration: Duration| {
+        let seconds = duration.as_secs();
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    };
+    let increment = if increment.is_zero() { String::new() } else { format(increment) };
+    Some(format!("{}/{}/", format(time), increment))
+}
+
+/// GGF and NBoard write squares in upper case and passes as "PA".
+pub fn format_move(mv: Move) -> String {
+    match mv {
+        Move::Place(..) => mv.to_string().to_ascii_uppercase(),
+        Move::Pass => "PA".to_string(),
+    }
+}
+
+// Reads the game at the start of `text`, returning it with the text after it.
+fn parse_game(text: &str) -> Result<(GgfGame, &str), String> {
+    let mut rest = text.strip_prefix("(;").ok_or("game is not in GGF format")?;
+    let mut ggf = GgfGame::new(Game::new(), "", "");
+    ggf.result = None;
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix(";)") {
+            return Ok((ggf, after));
+        }
+        let open = rest.find('[').ok_or("unterminated GGF game")?;
+        let name = rest[..open].trim();
+        let (value, after) = read_value(&rest[open + 1..])?;
+        rest = after;
+        match name {
+            "PC" => ggf.place = Some(value),
+            "DT" => ggf.date = Some(value),
+            "PB" => ggf.black.name = value,
+            "PW" => ggf.white.name = value,
+            "RB" => ggf.black.rating = value.trim().parse().ok(),
+            "RW" => ggf.white.rating = value.trim().parse().ok(),
+            "TI" => ggf.time_control = Some(value),
+            "RE" => ggf.result = Some(value),
+            "BO" => {
+                if !ggf.game.history().is_empty() {
+                    return Err("the board comes after the moves".to_string());
+                }
+                let (board, side) = parse_board(&value)?;
+                ggf.game = Game::from_position(board, side);
+            }
+            "B" | "W" => {
+                let player = if name == "B" { Disc::Black } else { Disc::White };
+                play(&mut ggf, player, &value)?;
+            }
+            _ => {}
+        }
+    }
+}
+
+// A value runs to the next unescaped `]`; a backslash escapes the next character.
+fn read_value(text: &str) -> Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            ']' => return Ok((value, &text[index + 1..])),
+            '\\' => value.extend(chars.next().map(|(_, c)| c)),
+            c => value.push(c),
+        }
+    }
+    Err("unterminated GGF property".to_string())
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+// `MOVE/EVAL/SECONDS`, where the annotations are optional and may be empty.
+fn play(ggf: &mut GgfGame, player: Disc, value: &str) -> Result<(), String> {
+    let mut fields = value.split('/');
+    let text = fields.next().unwrap_or_default().trim();
+    let mv: Move = text.parse().map_err(|_| format!("malformed move '{}'", text))?;
+    let eval = fields.next().and_then(|field| field.trim().parse().ok());
+    let time = fields
+        .next()
+        .and_then(|field| field.trim().parse::<f64>().ok())
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64);
+
+    let game = &mut ggf.game;
+    ggf.notes.resize(game.history().len(), MoveNote::default());
+    if game.current_turn() != player && game.must_pass() {
+        game.pass();
+        ggf.notes.push(MoveNote::default());
+    }
+    if game.current_turn() != player || !game.play(mv) {
+        return Err(format!("illegal move '{}'", text));
+    }
+    ggf.notes.push(MoveNote { eval, time });
+    Ok(())
+}
+
+// `BO[8 ---...O*... *]`: board size, 64 squares (`*` black, `O` white) and the side to move.
+fn parse_board(value: &str) -> Result<(Board, Disc), String> {
+    let mut fields = value.split_whitespace();
+    if fields.next() != Some("8") {
+        return Err("only 8x8 boards are supported".to_string());
+    }
+    let squares: String = fields.collect();
+    let mut obf: String = squares
+        .chars()
+        .map(|c| match c {
+            '*' => 'X',
+            'o' => 'O',
+            other => other,
+        })
+        .collect();
+    let side = obf.pop().ok_or("empty GGF board")?;
+    parse_position(&format!("{} {}", obf, side)).map_err(|e| e.to_string())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER_GAME: &str = "(;GM[Othello]PC[GGS/os]DT[2003.12.15_13:24:03.MST]PB[alice]PW[bob]RB[2012.50]\
+        RW[1890.00]TI[05:00//02:00]TY[8]RE[+4.000]\
+        BO[8 ---------------------------O*------*O--------------------------- *]\
+        B[F5//1.20]W[d6/-0.50/3.04]B[C3/1.00]KW[ignored];)";
+
+    #[test]
+    fn test_parse_server_game() {
+        let ggf = GgfGame::parse(SERVER_GAME).unwrap();
+        assert_eq!(ggf.place.as_deref(), Some("GGS/os"));
+        assert_eq!(ggf.date.as_deref(), Some("2003.12.15_13:24:03.MST"));
+        assert_eq!(ggf.black, GgfPlayer { name: "alice".to_string(), rating: Some(2012.5) });
+        assert_eq!(ggf.white.rating, Some(1890.0));
+        assert_eq!(ggf.time_control.as_deref(), Some("05:00//02:00"));
+        assert_eq!(ggf.black_margin(), Some(4.0));
+        assert_eq!(ggf.game.transcript(), "f5d6c3");
+        assert_eq!(ggf.notes[0], MoveNote { eval: None, time: Some(Duration::from_millis(1200)) });
+        assert_eq!(ggf.notes[1], MoveNote { eval: Some(-0.5), time: Some(Duration::from_millis(3040)) });
+        assert_eq!(ggf.notes[2], MoveNote { eval: Some(1.0), time: None });
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut game = Game::from_position_str(&format!("OX{} X", "-".repeat(62))).unwrap();
+        game.pass();
+        game.play("c1".parse().unwrap());
+        let mut ggf = GgfGame::new(game, "hard:0.5", "gtp:engine [x]");
+        ggf.white.rating = Some(1500.0);
+        ggf.time_control = time_control(&Clock::total(Duration::from_secs(300), Duration::ZERO));
+        ggf.notes = vec![MoveNote::default(), MoveNote { eval: Some(3.0), time: Some(Duration::from_millis(250)) }];
+        let text = ggf.to_string();
+        assert!(text.contains("PW[gtp:engine [x\\]]"), "{}", text);
+        // Empty squares go to the winner.
+        assert!(text.contains("TI[05:00//]TY[8]RE[-64.000]"), "{}", text);
+        assert!(text.ends_with("B[PA]W[C1/3.00/0.25];)"), "{}", text);
+
+        let read = GgfGame::parse(&text).unwrap();
+        assert_eq!(read.white, ggf.white);
+        assert_eq!(read.game.start_position(), ggf.game.start_position());
+        assert_eq!(read.game.history(), ggf.game.history());
+        assert_eq!(read.notes, ggf.notes);
+        assert_eq!(read.black_margin(), Some(-64.0));
+    }
+
+    #[test]
+    fn test_implied_pass() {
+        // Black has no move, so White's c1 follows an implied pass.
+        let ggf = GgfGame::parse(&format!("(;GM[Othello]BO[8 O*{} *]W[C1];)", "-".repeat(62))).unwrap();
+        assert_eq!(ggf.game.history().len(), 2);
+        assert_eq!(ggf.game.history()[0].mv, Move::Pass);
+        assert_eq!(ggf.notes.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_all_and_errors() {
+        let text = format!("2 {}\n{}\n", SERVER_GAME, "(;GM[Othello]B[f5];)");
+        let games = parse_all(&text).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].game.transcript(), "f5");
+        assert_eq!(games[1].result, None);
+
+        assert!(GgfGame::parse("(;GM[Othello]BO[8 bogus *];)").is_err());
+        assert!(GgfGame::parse("not a game").is_err());
+        assert!(GgfGame::parse("(;GM[Othello]B[f5]").is_err());
+        let error = parse_all("(;B[f5];)(;B[a1];)").unwrap_err();
+        assert!(error.to_string().starts_with("game 2: illegal move"), "{}", error);
+    }
+}
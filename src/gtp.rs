@@ -0,0 +1,498 @@
+//! A line protocol modelled on GTP (the Go Text Protocol), for match runners.
+//!
+//! Each command is one line, optionally preceded by a numeric id. Successful
+//! replies start with `=`, failures with `?`, and every reply ends with a blank
+//! line:
+//!
+//! ```text
+//! 1 play black f5         -> =1
+//! genmove white           -> = d6
+//! play black a1           -> ? illegal move
+//! final_score             -> = B+2
+//! ```
+//!
+//! Moves are written as squares (`f5`) or `pass`; colours as `black`/`b` or
+//! `white`/`w`. Only 8x8 boards exist.
+
+use crate::board::Disc;
+use crate::clock::{Clock, TimeControl};
+use crate::game::{Game, Move};
+use crate::nboard::ENGINE_NAME;
+use crate::player::Player;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+const COMMANDS: &[&str] = &[
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "undo",
+    "final_score",
+    "showboard",
+    "time_settings",
+    "time_left",
+];
+
+pub struct GtpEngine {
+    game: Game,
+    player: Box<dyn Player>,
+    clock: Clock,
+}
+
+impl GtpEngine {
+    /// `player` answers `genmove`.
+    pub fn new(player: Box<dyn Player>) -> Self {
+        GtpEngine { game: Game::new(), player, clock: Clock::unlimited() }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Handles one command line and writes the reply to `out`. Returns `Ok(false)`
+    /// after `quit`.
+    pub fn handle<W: Write>(&mut self, line: &str, out: &mut W) -> io::Result<bool> {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            return Ok(true);
+        }
+        let mut words = line.split_whitespace().peekable();
+        let id = words.next_if(|word| word.chars().all(|c| c.is_ascii_digit())).unwrap_or_default();
+        let command = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+
+        let reply = self.execute(command, &args);
+        match &reply {
+            Ok(text) if text.is_empty() => write!(out, "={}\n\n", id)?,
+            Ok(text) => write!(out, "={} {}\n\n", id, text)?,
+            Err(message) => write!(out, "?{} {}\n\n", id, message)?,
+        }
+        out.flush()?;
+        Ok(command != "quit")
+    }
+
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        match command {
+            "protocol_version" => Ok("2".to_string()),
+            "name" => Ok(ENGINE_NAME.to_string()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            "known_command" => Ok(COMMANDS.contains(&arg(args, 0)?).to_string()),
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" | "komi" => Ok(String::new()),
+            "boardsize" => match arg(args, 0)? {
+                "8" => Ok(String::new()),
+                _ => Err("unacceptable size".to_string()),
+            },
+            "clear_board" => {
+                self.game = Game::new();
+                Ok(String::new())
+            }
+            "play" => {
+                let color = parse_color(arg(args, 0)?)?;
+                let mv: Move = arg(args, 1)?.parse().map_err(|_| "invalid coordinate".to_string())?;
+                let mut game = self.turn_for(color)?;
+                if !game.play(mv) {
+                    return Err("illegal move".to_string());
+                }
+                self.game = game;
+                Ok(String::new())
+            }
+            "genmove" => {
+                let color = parse_color(arg(args, 0)?)?;
+                let mut game = self.turn_for(color)?;
+                if game.is_game_over() {
+                    return Ok(Move::Pass.to_string());
+                }
+                // The controller may correct our clock with `time_left`.
+                let start = Instant::now();
+                let mv = self.player.choose_move(&game, &self.clock).ok_or("engine failed to move")?;
+                self.clock.spend(color, start.elapsed());
+                if !game.play(mv) {
+                    return Err(format!("engine chose illegal move {}", mv));
+                }
+                self.game = game;
+                Ok(mv.to_string())
+            }
+            "undo" => {
+                if self.game.undo() {
+                    Ok(String::new())
+                } else {
+                    Err("cannot undo".to_string())
+                }
+            }
+            "final_score" => {
+                // Empty squares only go to the winner once the game is over.
+                let (black, white) = self.game.count_discs();
+                let margin = match self.game.result() {
+                    Some(result) => result.margin(Disc::Black),
+                    None => black as i32 - white as i32,
+                };
+                Ok(match margin {
+                    0 => "0".to_string(),
+                    m if m > 0 => format!("B+{}", m),
+                    m => format!("W+{}", -m),
+                })
+            }
+            "showboard" => Ok(format!("\n{}", self.board_text())),
+            "time_settings" => {
+                let main = parse_seconds(arg(args, 0)?)?;
+                let byo_yomi = parse_seconds(arg(args, 1)?)?;
+                let stones: u32 = arg(args, 2)?.parse().map_err(|_| "invalid stones".to_string())?;
+                self.clock = match (main.is_zero(), stones) {
+                    // GTP's "byo-yomi time with no stones" means no time limit.
+                    (_, 0) if !byo_yomi.is_zero() => Clock::unlimited(),
+                    (true, _) if !byo_yomi.is_zero() => Clock::per_move(byo_yomi / stones),
+                    (false, _) => Clock::total(main, Duration::ZERO),
+                    _ => Clock::unlimited(),
+                };
+                Ok(String::new())
+            }
+            "time_left" => {
+                let color = parse_color(arg(args, 0)?)?;
+                let time = parse_seconds(arg(args, 1)?)?;
+                self.clock.set_remaining(color, time);
+                Ok(String::new())
+            }
+            _ => Err("unknown command".to_string()),
+        }
+    }
+
+    // A copy of the game with `color` on move, passing for the other side if it has
+    // no move. The caller keeps it only once its command succeeds, so a rejected
+    // move leaves the game, forced pass included, as it was.
+    fn turn_for(&self, color: Disc) -> Result<Game, String> {
+        let mut game = self.game.clone();
+        if game.current_turn() != color && game.must_pass() {
+            game.pass();
+        }
+        if game.current_turn() != color && !game.is_game_over() {
+            return Err("illegal move: not this colour's turn".to_string());
+        }
+        Ok(game)
+    }
+
+    fn board_text(&self) -> String {
+        let mut text = String::from("  a b c d e f g h\n");
+        for row in 0..8 {
+            text.push_str(&(row + 1).to_string());
+            for col in 0..8 {
+                text.push(' ');
+                text.push(match self.game.board().get_disc(row, col) {
+                    Some(Disc::Black) => 'X',
+                    Some(Disc::White) => 'O',
+                    _ => '.',
+                });
+            }
+            text.push('\n');
+        }
+        let (black, white) = self.game.count_discs();
+        let side = if self.game.current_turn() == Disc::Black { "Black" } else { "White" };
+        text.push_str(&format!("X: {}  O: {}  {} to move", black, white, side));
+        text
+    }
+}
+
+/// Reads commands from `input` until it ends or `quit` arrives.
+pub fn run<R: BufRead, W: Write>(input: R, mut output: W, player: Box<dyn Player>) -> io::Result<()> {
+    let mut engine = GtpEngine::new(player);
+    for line in input.lines() {
+        if !engine.handle(&line?, &mut output)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// A `Player` backed by an engine that speaks this protocol, usually an external
+/// process. The engine's board is kept in step with the game by replaying moves,
+/// so games must start from the initial position.
+pub struct GtpClient {
+    name: String,
+    reader: Box<dyn BufRead + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Option<Child>,
+    // Moves the engine has seen since its last `clear_board`, or `None` before the
+    // first game.
+    sent: Option<Vec<Move>>,
+}
+
+impl GtpClient {
+    /// Talks to an engine over an existing connection.
+    pub fn new(name: &str, reader: Box<dyn BufRead + Send>, writer: Box<dyn Write + Send>) -> Self {
+        GtpClient { name: name.to_string(), reader, writer, child: None, sent: None }
+    }
+
+    /// Starts `program` and checks that it accepts an 8x8 board.
+    pub fn spawn(name: &str, program: &str, args: &[&str]) -> io::Result<Self> {
+        let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let reader = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let writer = child.stdin.take().expect("stdin is piped");
+        let mut client = GtpClient::new(name, Box::new(reader), Box::new(writer));
+        client.child = Some(child);
+        client
+            .send("boardsize 8")?
+            .map_err(|message| io::Error::other(format!("{}: {}", name, message)))?;
+        Ok(client)
+    }
+
+    /// Sends one command and returns the engine's answer: `Ok` for `=` replies,
+    /// `Err` with the message for `?` replies.
+    pub fn send(&mut self, command: &str) -> io::Result<Result<String, String>> {
+        writeln!(self.writer, "{}", command)?;
+        self.writer.flush()?;
+        self.read_reply()
+    }
+
+    fn read_reply(&mut self) -> io::Result<Result<String, String>> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "engine closed the connection"));
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                if reply.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if !reply.is_empty() {
+                reply.push('\n');
+            }
+            reply.push_str(line);
+        }
+
+        let text = |rest: &str| rest.trim_start_matches(|c: char| c.is_ascii_digit()).trim().to_string();
+        if let Some(rest) = reply.strip_prefix('=') {
+            Ok(Ok(text(rest)))
+        } else if let Some(rest) = reply.strip_prefix('?') {
+            Ok(Err(text(rest)))
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected reply '{}'", reply)))
+        }
+    }
+
+    // Brings the engine's board up to `game`, starting over when `game` does not
+    // continue what the engine has seen.
+    fn sync(&mut self, game: &Game, clock: &Clock) -> io::Result<Result<(), String>> {
+        let moves: Vec<Move> = game.history().iter().map(|record| record.mv).collect();
+        let continues = self.sent.as_ref().is_some_and(|sent| moves.starts_with(sent));
+        if !continues {
+            if let Err(message) = self.send("clear_board")? {
+                return Ok(Err(message));
+            }
+            let settings = match clock.control() {
+                TimeControl::Unlimited => None,
+                TimeControl::PerMove(time) => Some(format!("time_settings 0 {} 1", time.as_secs_f64())),
+                TimeControl::Total { time, .. } => Some(format!("time_settings {} 0 0", time.as_secs_f64())),
+            };
+            if let Some(settings) = settings {
+                // Engines that do not manage time may refuse; that is not an error.
+                self.send(&settings)?.ok();
+            }
+            self.sent = Some(Vec::new());
+        }
+
+        let seen = self.sent.as_ref().map_or(0, Vec::len);
+        for record in &game.history()[seen..] {
+            let command = format!("play {} {}", color_name(record.player), record.mv);
+            let reply = self.send(&command)?;
+            if let Err(message) = reply {
+                // The engine's board is now out of step; start over next time.
+                self.sent = None;
+                return Ok(Err(format!("{}: {}", command, message)));
+            }
+            self.sent.get_or_insert_with(Vec::new).push(record.mv);
+        }
+
+        let side = game.current_turn();
+        if let Some(remaining) = clock.remaining(side) {
+            self.send(&format!("time_left {} {} 0", color_name(side), remaining.as_secs_f64()))?.ok();
+        }
+        Ok(Ok(()))
+    }
+}
+
+impl Player for GtpClient {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Option<Move> {
+        if game.start_position() != Game::new().start_position() {
+            return None;
+        }
+        let side = game.current_turn();
+        let result = self.sync(game, clock).and_then(|synced| match synced {
+            Ok(()) => self.send(&format!("genmove {}", color_name(side))),
+            Err(message) => Ok(Err(message)),
+        });
+        let reply = match result {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(_)) | Err(_) => {
+                self.sent = None;
+                return None;
+            }
+        };
+        let mv: Move = reply.parse().ok()?;
+        self.sent.get_or_insert_with(Vec::new).push(mv);
+        Some(mv)
+    }
+}
+
+impl Drop for GtpClient {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        if let Some(child) = &mut self.child {
+            let _ = child.wait();
+        }
+    }
+}
+
+fn color_name(side: Disc) -> &'static str {
+    if side == Disc::Black { "black" } else { "white" }
+}
+
+fn arg<'a>(args: &[&'a str], index: usize) -> Result<&'a str, String> {
+    args.get(index).copied().ok_or_else(|| "missing argument".to_string())
+}
+
+fn parse_color(text: &str) -> Result<Disc, String> {
+    match text.to_ascii_lowercase().as_str() {
+        "b" | "black" => Ok(Disc::Black),
+        "w" | "white" => Ok(Disc::White),
+        _ => Err("invalid color".to_string()),
+    }
+}
+
+fn parse_seconds(text: &str) -> Result<Duration, String> {
+    let seconds: f64 = text.parse().map_err(|_| "invalid time".to_string())?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err("invalid time".to_string());
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CpuConfig;
+    use crate::player::{CpuLevel, CpuPlayer};
+
+    fn engine() -> GtpEngine {
+        let config = CpuConfig { book: None, seed: Some(1), ..CpuConfig::default() };
+        GtpEngine::new(Box::new(CpuPlayer::new(CpuLevel::Medium).with_config(config)))
+    }
+
+    fn talk(engine: &mut GtpEngine, commands: &[&str]) -> String {
+        let mut out = Vec::new();
+        for command in commands {
+            engine.handle(command, &mut out).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_ids_and_errors() {
+        let mut engine = engine();
+        assert_eq!(talk(&mut engine, &["1 name"]), "=1 gemini-claude-othello\n\n");
+        assert_eq!(talk(&mut engine, &["boardsize 19"]), "? unacceptable size\n\n");
+        assert_eq!(talk(&mut engine, &["7 frobnicate"]), "?7 unknown command\n\n");
+        assert_eq!(talk(&mut engine, &["known_command genmove"]), "= true\n\n");
+        assert_eq!(talk(&mut engine, &["# comment", ""]), "");
+    }
+
+    #[test]
+    fn test_play_and_genmove() {
+        let mut engine = engine();
+        assert_eq!(talk(&mut engine, &["play black f5"]), "=\n\n");
+        assert_eq!(talk(&mut engine, &["play white a1"]), "? illegal move\n\n");
+        assert_eq!(talk(&mut engine, &["play black d6"]), "? illegal move: not this colour's turn\n\n");
+        assert_eq!(engine.game().transcript(), "f5");
+
+        let reply = talk(&mut engine, &["genmove w"]);
+        assert!(reply.starts_with("= ") && reply.ends_with("\n\n"));
+        assert_eq!(engine.game().history().len(), 2);
+
+        talk(&mut engine, &["undo", "undo"]);
+        assert_eq!(engine.game().history().len(), 0);
+        assert_eq!(talk(&mut engine, &["undo"]), "? cannot undo\n\n");
+    }
+
+    #[test]
+    fn test_illegal_play_leaves_game_unchanged() {
+        let mut engine = engine();
+        // Black has no move; White can take b1 by playing c1.
+        engine.game = Game::from_position_str(&format!("OX{} X", "-".repeat(62))).unwrap();
+        assert_eq!(talk(&mut engine, &["play white h8"]), "? illegal move\n\n");
+        assert!(engine.game().history().is_empty());
+        assert_eq!(engine.game().current_turn(), Disc::Black);
+
+        assert_eq!(talk(&mut engine, &["play white c1"]), "=\n\n");
+        assert_eq!(engine.game().history().len(), 2);
+        assert_eq!(engine.game().history()[0].mv, Move::Pass);
+    }
+
+    #[test]
+    fn test_final_score_and_showboard() {
+        let mut engine = engine();
+        assert_eq!(talk(&mut engine, &["final_score"]), "= 0\n\n");
+        talk(&mut engine, &["play b f5"]);
+        assert_eq!(talk(&mut engine, &["final_score"]), "= B+3\n\n");
+
+        let board = talk(&mut engine, &["showboard"]);
+        assert!(board.contains("5 . . . X X X . .\n"), "{}", board);
+        assert!(board.contains("White to move"));
+
+        engine.game = Game::from_position_str(&format!("OOO{} X", "-".repeat(61))).unwrap();
+        assert_eq!(talk(&mut engine, &["final_score"]), "= W+64\n\n");
+    }
+
+    #[test]
+    fn test_client_plays_against_engine() {
+        let (engine_reader, client_writer) = io::pipe().unwrap();
+        let (client_reader, engine_writer) = io::pipe().unwrap();
+        let server = std::thread::spawn(move || {
+            let config = CpuConfig { book: None, seed: Some(2), ..CpuConfig::default() };
+            let player = CpuPlayer::new(CpuLevel::Easy).with_config(config);
+            run(BufReader::new(engine_reader), engine_writer, Box::new(player)).unwrap();
+        });
+
+        let mut client = GtpClient::new("easy", Box::new(BufReader::new(client_reader)), Box::new(client_writer));
+        assert_eq!(client.send("boardsize 8").unwrap(), Ok(String::new()));
+        assert_eq!(client.send("boardsize 10").unwrap(), Err("unacceptable size".to_string()));
+
+        let mut game = Game::new();
+        for _ in 0..2 {
+            game.play(game.legal_moves()[0]);
+            let mv = client.choose_move(&game, &Clock::unlimited()).unwrap();
+            assert!(game.play(mv));
+        }
+        // A new game resets the engine's board.
+        let mut game = Game::new();
+        let mv = client.choose_move(&game, &Clock::unlimited()).unwrap();
+        assert!(game.play(mv));
+
+        drop(client);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_clear_board_and_time() {
+        let mut engine = engine();
+        talk(&mut engine, &["play b f5", "clear_board", "time_settings 60 0 0", "time_left b 30 0"]);
+        assert_eq!(engine.game().history().len(), 0);
+        assert_eq!(engine.clock.remaining(Disc::Black), Some(Duration::from_secs(30)));
+        assert_eq!(talk(&mut engine, &["time_settings x 0 0"]), "? invalid time\n\n");
+    }
+}
@@ -0,0 +1,31 @@
+//! Othello rules engine and CPU players.
+//!
+//! The crossterm TUI in `main.rs` is a thin front end over this crate; bots,
+//! analysis scripts and servers can link against the same rules engine.
+
+pub mod analysis;
+pub mod arena;
+pub mod board;
+pub mod book;
+pub mod clock;
+pub mod cpu;
+pub mod endgame;
+pub mod eval;
+pub mod game;
+pub mod ggf;
+pub mod gtp;
+pub mod nboard;
+pub mod notation;
+pub mod pattern;
+pub mod perft;
+pub mod player;
+pub mod replay;
+pub mod save;
+pub mod tournament;
+pub mod tt;
+pub mod wthor;
+
+pub use board::{Board, Disc};
+pub use game::{Game, GameResult, Move};
+pub use clock::Clock;
+pub use player::{CpuLevel, CpuPlayer, Player, PlayerType};
@@ -1,9 +1,5 @@
-mod board;
-mod cpu;
-mod game;
-mod player;
+mod cli;
 
-use board::{Board, Disc};
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
     event::{self, Event, KeyCode, KeyModifiers},
@@ -11,44 +7,167 @@ use crossterm::{
     style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use game::Game;
-use player::{Player, PlayerType, CpuLevel};
+use gemini_claude_othello::arena;
+use gemini_claude_othello::analysis::{self, GameAnalysis, Judgement, MoveAnalysis, DEFAULT_ANALYSIS_DEPTH};
+use gemini_claude_othello::cpu::{CpuConfig, SearchLimits};
+use gemini_claude_othello::eval::Evaluator;
+use gemini_claude_othello::replay::Replay;
+use gemini_claude_othello::save::{list_saves, SavedGame, SAVE_EXTENSION};
+use gemini_claude_othello::{Board, Clock, CpuLevel, CpuPlayer, Disc, Game, GameResult, Move, Player, PlayerType};
+use std::env;
 use std::io::{stdout, Stdout};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut options = None;
+    if cli::is_play_options(&args) {
+        match cli::parse_play_options(&args) {
+            Ok(parsed) => options = Some(parsed),
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(2);
+            }
+        }
+    } else if !args.is_empty() {
+        if let Err(message) = cli::run(&args) {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen, Hide)?;
     terminal::enable_raw_mode()?;
 
-    let game_mode = select_game_mode(&mut stdout)?;
-
-    let mut game = Game::new();
-    let player1 = Player::new(PlayerType::Human, Disc::Black);
-    let player2 = Player::new(game_mode, Disc::White);
-    let mut cursor_pos = (0, 0);
-
-    let result = run_game_loop(&mut stdout, &mut game, &player1, &player2, &mut cursor_pos);
+    let result = play(&mut stdout, options);
 
     terminal::disable_raw_mode()?;
     execute!(stdout, Show, LeaveAlternateScreen)?;
     result
 }
 
-fn select_game_mode(stdout: &mut Stdout) -> std::io::Result<PlayerType> {
+fn play(stdout: &mut Stdout, options: Option<cli::PlayOptions>) -> std::io::Result<()> {
+    let (mut game, black, white, config, cpu_options, delay, clock) = match options {
+        Some(cli::PlayOptions { replay: Some(game), config, .. }) => {
+            return run_replay(stdout, &mut Replay::new(&game), config.evaluator.as_ref());
+        }
+        Some(options) => {
+            let cli::PlayOptions { game, black, white, config, cpu_options, delay, clock, .. } = options;
+            (game, black, white, config, cpu_options, delay, clock)
+        }
+        None => match select_game_mode(stdout)? {
+            GameSetup::New { black, white } => {
+                let config = CpuConfig::default();
+                (Game::new(), black, white, config, Vec::new(), cli::DEFAULT_MOVE_DELAY, Clock::unlimited())
+            }
+            GameSetup::Resume(saved, config) => {
+                let SavedGame { game, black, white, clock, cpu_options } = saved;
+                (game, black, white, config, cpu_options, cli::DEFAULT_MOVE_DELAY, clock)
+            }
+            GameSetup::Replay(saved) => {
+                let config = CpuConfig::default();
+                return run_replay(stdout, &mut Replay::new(&saved.game), config.evaluator.as_ref());
+            }
+        },
+    };
+    let seat = |player_type| match player_type {
+        PlayerType::Human => Seat::Human,
+        PlayerType::Cpu(level) => Seat::Engine(Box::new(CpuPlayer::new(level).with_config(config.clone()))),
+    };
+    let mut seats = [seat(black), seat(white)];
+    // Nobody to wait for when no human plays, so moves are paced for watching.
+    let watching = seats.iter().all(|seat| matches!(seat, Seat::Engine(_)));
+    let mut spectator = watching.then_some(Spectator { delay, paused: false });
+
+    let info = SaveInfo { players: [black, white], cpu_options };
+    run_game_loop(stdout, &mut game, &mut seats, &info, &mut spectator, clock, config.evaluator.as_ref())
+}
+
+enum GameSetup {
+    New { black: PlayerType, white: PlayerType },
+    // With the CPU settings read from the save.
+    Resume(SavedGame, CpuConfig),
+    Replay(SavedGame),
+}
+
+// Who plays each side: the person at the keyboard, whose moves and commands the
+// game loop reads itself, or any `Player`, such as a built-in CPU or a custom bot.
+enum Seat {
+    Human,
+    Engine(Box<dyn Player>),
+}
+
+fn seat_index(side: Disc) -> usize {
+    if side == Disc::Black { 0 } else { 1 }
+}
+
+struct Spectator {
+    delay: Duration,
+    paused: bool,
+}
+
+const DELAY_STEP: Duration = Duration::from_millis(250);
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+// Saves go to $OTHELLO_SAVE_DIR, falling back to ~/.gemini-claude-othello/saves.
+fn save_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("OTHELLO_SAVE_DIR") {
+        return PathBuf::from(dir);
+    }
+    match env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+        Some(home) => PathBuf::from(home).join(".gemini-claude-othello").join("saves"),
+        None => PathBuf::from("saves"),
+    }
+}
+
+fn select_game_mode(stdout: &mut Stdout) -> std::io::Result<GameSetup> {
+    let mut error = String::new();
     loop {
-        draw_game_mode_selection(stdout, "")?;
+        draw_game_mode_selection(stdout, &error)?;
         if let Event::Key(key_event) = event::read()? {
             match key_event.code {
-                KeyCode::Char('1') => return Ok(PlayerType::Human),
-                KeyCode::Char('2') => return Ok(select_cpu_level(stdout)?),
-                KeyCode::Char('q') => {
-                    if key_event.modifiers.contains(KeyModifiers::CONTROL) {
-                        terminal::disable_raw_mode()?;
-                        execute!(stdout, Show, LeaveAlternateScreen)?;
-                        std::process::exit(0);
+                KeyCode::Char('1') => {
+                    return Ok(GameSetup::New { black: PlayerType::Human, white: PlayerType::Human });
+                }
+                KeyCode::Char('2') => {
+                    if let Some(level) = select_cpu_level(stdout, "Select CPU difficulty:")? {
+                        if let Some(side) = select_side(stdout)? {
+                            let cpu = PlayerType::Cpu(level);
+                            return Ok(match side {
+                                Disc::Black => GameSetup::New { black: PlayerType::Human, white: cpu },
+                                _ => GameSetup::New { black: cpu, white: PlayerType::Human },
+                            });
+                        }
+                    }
+                    error.clear();
+                }
+                KeyCode::Char(c @ ('3' | '5')) => match select_saved_game(stdout) {
+                    Ok(Some(saved)) if c == '3' => match cli::parse_cpu_options(&saved.cpu_options) {
+                        Ok(config) => return Ok(GameSetup::Resume(saved, config)),
+                        Err(e) => error = format!("Could not resume: {}", e.lines().next().unwrap_or("")),
+                    },
+                    Ok(Some(saved)) => return Ok(GameSetup::Replay(saved)),
+                    Ok(None) => error.clear(),
+                    Err(e) => error = e,
+                },
+                KeyCode::Char('4') => {
+                    if let Some(black) = select_cpu_level(stdout, "Select difficulty for Black (◯):")? {
+                        if let Some(white) = select_cpu_level(stdout, "Select difficulty for White (●):")? {
+                            return Ok(GameSetup::New { black: PlayerType::Cpu(black), white: PlayerType::Cpu(white) });
+                        }
                     }
+                    error.clear();
+                }
+                KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    terminal::disable_raw_mode()?;
+                    execute!(stdout, Show, LeaveAlternateScreen)?;
+                    std::process::exit(0);
                 }
                 _ => {}
             }
@@ -56,87 +175,343 @@ fn select_game_mode(stdout: &mut Stdout) -> std::io::Result<PlayerType> {
     }
 }
 
-fn select_cpu_level(stdout: &mut Stdout) -> std::io::Result<PlayerType> {
+// `Ok(None)` means "go back".
+fn select_cpu_level(stdout: &mut Stdout, title: &str) -> std::io::Result<Option<CpuLevel>> {
     loop {
-        draw_cpu_level_selection(stdout, "")?;
+        draw_cpu_level_selection(stdout, title)?;
         if let Event::Key(key_event) = event::read()? {
             match key_event.code {
-                KeyCode::Char('1') => return Ok(PlayerType::Cpu(CpuLevel::Easy)),
-                KeyCode::Char('2') => return Ok(PlayerType::Cpu(CpuLevel::Medium)),
-                KeyCode::Char('3') => return Ok(PlayerType::Cpu(CpuLevel::Hard)),
-                KeyCode::Char('b') => return select_game_mode(stdout),
-                KeyCode::Char('q') => {
-                    if key_event.modifiers.contains(KeyModifiers::CONTROL) {
-                        terminal::disable_raw_mode()?;
-                        execute!(stdout, Show, LeaveAlternateScreen)?;
-                        std::process::exit(0);
+                KeyCode::Char('1') => return Ok(Some(CpuLevel::Easy)),
+                KeyCode::Char('2') => return Ok(Some(CpuLevel::Medium)),
+                KeyCode::Char('3') => return Ok(Some(CpuLevel::Hard)),
+                KeyCode::Char('b') => return Ok(None),
+                KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    terminal::disable_raw_mode()?;
+                    execute!(stdout, Show, LeaveAlternateScreen)?;
+                    std::process::exit(0);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Returns the colour the human plays; `Ok(None)` means "go back".
+fn select_side(stdout: &mut Stdout) -> std::io::Result<Option<Disc>> {
+    loop {
+        draw_side_selection(stdout)?;
+        if let Event::Key(key_event) = event::read()? {
+            match key_event.code {
+                KeyCode::Char('1') => return Ok(Some(Disc::Black)),
+                KeyCode::Char('2') => return Ok(Some(Disc::White)),
+                KeyCode::Char('b') => return Ok(None),
+                KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    terminal::disable_raw_mode()?;
+                    execute!(stdout, Show, LeaveAlternateScreen)?;
+                    std::process::exit(0);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+const MAX_LISTED_SAVES: usize = 9;
+
+// Lets the user pick one of the most recent saves. `Ok(None)` means "go back"; errors
+// are returned as a message for the menu.
+fn select_saved_game(stdout: &mut Stdout) -> Result<Option<SavedGame>, String> {
+    let dir = save_dir();
+    let paths = list_saves(&dir).map_err(|e| format!("Could not read {}: {}", dir.display(), e))?;
+    if paths.is_empty() {
+        return Err(format!("No saved games in {}", dir.display()));
+    }
+    let saves: Vec<(PathBuf, std::io::Result<SavedGame>)> = paths
+        .into_iter()
+        .take(MAX_LISTED_SAVES)
+        .map(|path| {
+            let saved = SavedGame::load(&path);
+            (path, saved)
+        })
+        .collect();
+
+    let mut error = String::new();
+    loop {
+        draw_saved_game_selection(stdout, &saves, &error).map_err(|e| e.to_string())?;
+        if let Event::Key(key_event) = event::read().map_err(|e| e.to_string())? {
+            match key_event.code {
+                KeyCode::Char(c @ '1'..='9') => {
+                    let index = c as usize - '1' as usize;
+                    match saves.get(index) {
+                        Some((_, Ok(saved))) => return Ok(Some(saved.clone())),
+                        Some((path, Err(e))) => error = format!("{}: {}", path.display(), e),
+                        None => {}
                     }
                 }
+                KeyCode::Char('b') => return Ok(None),
                 _ => {}
             }
         }
     }
 }
 
+fn describe_player(player: PlayerType) -> String {
+    match player {
+        PlayerType::Human => "Player".to_string(),
+        PlayerType::Cpu(level) => format!("CPU ({:?})", level),
+    }
+}
+
+// What save files record besides the game and its clock.
+struct SaveInfo {
+    players: [PlayerType; 2],
+    cpu_options: Vec<String>,
+}
+
+fn save_game(game: &Game, clock: &Clock, info: &SaveInfo, path: &Path) -> std::io::Result<()> {
+    SavedGame::new(game.clone(), info.players[0], info.players[1])
+        .with_clock(clock.clone())
+        .with_cpu_options(info.cpu_options.clone())
+        .save(path)
+}
+
+// Saves under a new timestamped name and returns a status message.
+fn save_snapshot(game: &Game, clock: &Clock, info: &SaveInfo) -> String {
+    let path = save_dir().join(format!("game-{}.{}", unix_time(), SAVE_EXTENSION));
+    match save_game(game, clock, info, &path) {
+        Ok(()) => format!("Game saved to {}", path.display()),
+        Err(e) => format!("Could not save game: {}", e),
+    }
+}
+
+// Keeps unfinished games on quit so they can be resumed from the menu.
+fn autosave(game: &Game, clock: &Clock, info: &SaveInfo) -> std::io::Result<()> {
+    if game.can_undo() {
+        save_game(game, clock, info, &save_dir().join(format!("autosave.{}", SAVE_EXTENSION)))?;
+    }
+    Ok(())
+}
+
+// `info` is what save files record about the seats and settings.
 fn run_game_loop(
     stdout: &mut Stdout,
     game: &mut Game,
-    player1: &Player,
-    player2: &Player,
-    cursor_pos: &mut (u16, u16),
+    seats: &mut [Seat; 2],
+    info: &SaveInfo,
+    spectator: &mut Option<Spectator>,
+    mut clock: Clock,
+    evaluator: &dyn Evaluator,
 ) -> std::io::Result<()> {
+    let mut cursor_pos = (0, 0);
+    let mut status = String::new();
+    // The side that ran out of time, which loses as in `arena::play_game`.
+    let mut flagged = None;
     loop {
-        draw_board(stdout, game.board(), *cursor_pos)?;
-        draw_info(stdout, game)?;
-
-        let current_player_disc = game.current_turn();
-        let current_player = if current_player_disc == Disc::Black {
-            player1
-        } else {
-            player2
+        draw_board(stdout, game.board(), cursor_pos)?;
+        draw_info(stdout, game, &help_text(spectator.as_ref()))?;
+        draw_clock(stdout, &clock)?;
+        draw_status(stdout, &status)?;
+        status.clear();
+
+        let result = match flagged {
+            Some(side) => Some(arena::forfeit(game.clone(), side).result),
+            None => game.result(),
         };
+        if let Some(result) = result {
+            if let Some(side) = flagged {
+                let symbol = if side == Disc::Black { "◯" } else { "●" };
+                draw_status(stdout, &format!("{} ran out of time.", symbol))?;
+            }
+            draw_game_over(stdout, &result, &game.transcript())?;
+            match wait_after_game()? {
+                GameOverInput::Replay => run_replay(stdout, &mut Replay::new(game), evaluator)?,
+                GameOverInput::Save => status = save_snapshot(game, &clock, info),
+                GameOverInput::Quit => break,
+            }
+            continue;
+        }
 
-        if game.is_game_over() {
-            draw_game_over(stdout, game)?;
-            break;
+        if game.must_pass() {
+            game.play(Move::Pass);
+            continue;
         }
 
-        let valid_moves = game.get_valid_moves(current_player_disc);
-        if valid_moves.is_empty() {
-            game.current_turn = if current_player_disc == Disc::Black {
-                Disc::White
-            } else {
-                Disc::Black
-            };
+        let side = game.current_turn();
+        let (mv, elapsed) = match &mut seats[seat_index(side)] {
+            Seat::Human => match timed(|| get_human_input(stdout, game, &mut cursor_pos, &clock))? {
+                (HumanInput::Move(row, col), elapsed) => (Move::Place(row, col), elapsed),
+                (HumanInput::Undo, _) => {
+                    take_back(game, seats, Game::undo);
+                    continue;
+                }
+                (HumanInput::Redo, _) => {
+                    take_back(game, seats, Game::redo);
+                    continue;
+                }
+                (HumanInput::Save, _) => {
+                    status = save_snapshot(game, &clock, info);
+                    continue;
+                }
+                (HumanInput::Quit, _) => return autosave(game, &clock, info),
+            },
+            Seat::Engine(player) => match spectator {
+                Some(spectator) => match wait_between_moves(stdout, spectator)? {
+                    SpectatorInput::Continue => {
+                        show_cpu_thinking(stdout, game, player.as_mut(), &clock, Duration::ZERO)?
+                    }
+                    SpectatorInput::Save => {
+                        status = save_snapshot(game, &clock, info);
+                        continue;
+                    }
+                    SpectatorInput::Quit => return autosave(game, &clock, info),
+                },
+                None => show_cpu_thinking(stdout, game, player.as_mut(), &clock, MIN_THINKING_TIME)?,
+            },
+        };
+
+        if !clock.spend(side, elapsed) {
+            flagged = Some(side);
             continue;
         }
+        game.play(mv);
+    }
+    Ok(())
+}
+
+// Time left for each side beside the disc counts, under a total time control.
+fn draw_clock(stdout: &mut Stdout, clock: &Clock) -> std::io::Result<()> {
+    if let (Some(black), Some(white)) = (clock.remaining(Disc::Black), clock.remaining(Disc::White)) {
+        let format_time = |time: Duration| format!("{}:{:02}", time.as_secs() / 60, time.as_secs() % 60);
+        execute!(
+            stdout,
+            MoveTo(20, 11),
+            Print(format!("Time ◯ {} | ● {}", format_time(black), format_time(white)))
+        )?;
+    }
+    Ok(())
+}
 
-        let (row, col) = match current_player.player_type() {
-            PlayerType::Human => {
-                let human_move = get_human_input(stdout, game, cursor_pos, current_player_disc)?;
-                if human_move.is_none() {
-                    // 'q' was pressed
-                    return Ok(());
+// Runs `input` and times it, for charging a human's thinking to the clock.
+fn timed<T>(input: impl FnOnce() -> std::io::Result<T>) -> std::io::Result<(T, Duration)> {
+    let started = Instant::now();
+    let value = input()?;
+    Ok((value, started.elapsed()))
+}
+
+enum HumanInput {
+    Move(usize, usize),
+    Undo,
+    Redo,
+    Save,
+    Quit,
+}
+
+enum SpectatorInput {
+    Continue,
+    Save,
+    Quit,
+}
+
+enum GameOverInput {
+    Replay,
+    Save,
+    Quit,
+}
+
+fn wait_after_game() -> std::io::Result<GameOverInput> {
+    loop {
+        if let Event::Key(key_event) = event::read()? {
+            match key_event.code {
+                KeyCode::Char('v') => return Ok(GameOverInput::Replay),
+                KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(GameOverInput::Save);
                 }
-                human_move.unwrap()
+                KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(GameOverInput::Quit);
+                }
+                _ => {}
             }
-            PlayerType::Cpu(level) => {
-                show_cpu_thinking(stdout, game, *level)?;
-                player2.get_move(game)
+        }
+    }
+}
+
+// Waits out the move delay before the next CPU move, or, while paused, until the
+// spectator steps or resumes.
+fn wait_between_moves(stdout: &mut Stdout, spectator: &mut Spectator) -> std::io::Result<SpectatorInput> {
+    let mut started = Instant::now();
+    loop {
+        execute!(
+            stdout,
+            MoveTo(0, 12),
+            Clear(ClearType::CurrentLine),
+            Print(help_text(Some(spectator)))
+        )?;
+        if !spectator.paused {
+            let remaining = spectator.delay.saturating_sub(started.elapsed());
+            if remaining.is_zero() || !event::poll(remaining)? {
+                return Ok(SpectatorInput::Continue);
             }
-        };
+        }
+        if let Event::Key(key_event) = event::read()? {
+            match key_event.code {
+                KeyCode::Char('p') | KeyCode::Char(' ') => {
+                    spectator.paused = !spectator.paused;
+                    started = Instant::now();
+                }
+                KeyCode::Char('n') | KeyCode::Enter if spectator.paused => return Ok(SpectatorInput::Continue),
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    spectator.delay = (spectator.delay + DELAY_STEP).min(MAX_DELAY);
+                }
+                KeyCode::Char('-') => spectator.delay = spectator.delay.saturating_sub(DELAY_STEP),
+                KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(SpectatorInput::Save);
+                }
+                KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(SpectatorInput::Quit);
+                }
+                _ => {}
+            }
+        }
+    }
+}
 
-        game.make_move(row, col, current_player_disc);
+fn help_text(spectator: Option<&Spectator>) -> String {
+    match spectator {
+        None => "Use arrow keys to move, Enter/Space to place, 'u'/'r' to undo/redo, 'Ctrl+S' to save, 'Ctrl+Q' to quit."
+            .to_string(),
+        Some(spectator) if spectator.paused => {
+            "Paused: 'n' to step, 'p' to resume, 'Ctrl+S' to save, 'Ctrl+Q' to quit.".to_string()
+        }
+        Some(spectator) => format!(
+            "Delay {:.2}s ('+'/'-' to change), 'p' to pause, 'Ctrl+S' to save, 'Ctrl+Q' to quit.",
+            spectator.delay.as_secs_f64()
+        ),
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// Steps through the history with `step` (undo or redo), continuing past CPU turns so
+// that control comes back to a human player.
+fn take_back(game: &mut Game, seats: &[Seat; 2], step: fn(&mut Game) -> bool) {
+    while step(game) {
+        let side_to_move = &seats[seat_index(game.current_turn())];
+        let waiting_on_human = matches!(side_to_move, Seat::Human) && !game.must_pass();
+        if waiting_on_human {
+            break;
+        }
     }
-    Ok(())
 }
 
 fn get_human_input(
     stdout: &mut Stdout,
     game: &Game,
     cursor_pos: &mut (u16, u16),
-    player_disc: Disc,
-) -> std::io::Result<Option<(usize, usize)>> {
+    clock: &Clock,
+) -> std::io::Result<HumanInput> {
+    let player_disc = game.current_turn();
     loop {
         if let Event::Key(key_event) = event::read()? {
             match key_event.code {
@@ -144,21 +519,24 @@ fn get_human_input(
                 KeyCode::Down => cursor_pos.0 = (cursor_pos.0 + 1).min(7),
                 KeyCode::Left => cursor_pos.1 = cursor_pos.1.saturating_sub(1),
                 KeyCode::Right => cursor_pos.1 = (cursor_pos.1 + 1).min(7),
-                KeyCode::Enter | KeyCode::Char(' ') => {
-                    if game.is_valid_move(cursor_pos.0 as usize, cursor_pos.1 as usize, player_disc)
-                    {
-                        return Ok(Some((cursor_pos.0 as usize, cursor_pos.1 as usize)));
-                    }
+                KeyCode::Enter | KeyCode::Char(' ')
+                    if game.is_valid_move(cursor_pos.0 as usize, cursor_pos.1 as usize, player_disc) =>
+                {
+                    return Ok(HumanInput::Move(cursor_pos.0 as usize, cursor_pos.1 as usize));
                 }
-                KeyCode::Char('q') => {
-                    if key_event.modifiers.contains(KeyModifiers::CONTROL) {
-                        return Ok(None);
-                    }
+                KeyCode::Char('u') if game.can_undo() => return Ok(HumanInput::Undo),
+                KeyCode::Char('r') if game.can_redo() => return Ok(HumanInput::Redo),
+                KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(HumanInput::Save);
+                }
+                KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(HumanInput::Quit);
                 }
                 _ => {}
             }
             draw_board(stdout, game.board(), *cursor_pos)?;
-            draw_info(stdout, game)?;
+            draw_info(stdout, game, &help_text(None))?;
+            draw_clock(stdout, clock)?;
         }
     }
 }
@@ -194,12 +572,10 @@ fn get_disc_color(disc: Disc) -> Color {
     }
 }
 
-fn draw_info(stdout: &mut Stdout, game: &Game) -> std::io::Result<()> {
+fn draw_info(stdout: &mut Stdout, game: &Game, help_text: &str) -> std::io::Result<()> {
     let (black_count, white_count) = game.count_discs();
     let current_turn_symbol = if game.current_turn() == Disc::Black { "◯" } else { "●" };
-    let turn_color = if game.current_turn() == Disc::Black { Color::White } else { Color::White };
-    
-    let help_text = "Use arrow keys to move, Enter/Space to place, 'Ctrl+Q' to quit.";
+    let turn_color = Color::White;
 
     execute!(
         stdout,
@@ -234,21 +610,73 @@ fn draw_game_mode_selection(stdout: &mut Stdout, error: &str) -> std::io::Result
         Print("1. Player vs. Player"),
         MoveTo(2, 4),
         Print("2. Player vs. CPU"),
-        MoveTo(0, 6),
+        MoveTo(2, 5),
+        Print("3. Resume saved game"),
+        MoveTo(2, 6),
+        Print("4. CPU vs. CPU"),
+        MoveTo(2, 7),
+        Print("5. Replay saved game"),
+        MoveTo(0, 9),
         Print("Press 'Ctrl+Q' to quit."),
-        MoveTo(0, 8),
+        MoveTo(0, 11),
         SetForegroundColor(Color::Red),
         Print(error),
         ResetColor
     )
 }
 
-fn draw_cpu_level_selection(stdout: &mut Stdout, error: &str) -> std::io::Result<()> {
+fn draw_saved_game_selection(
+    stdout: &mut Stdout,
+    saves: &[(PathBuf, std::io::Result<SavedGame>)],
+    error: &str,
+) -> std::io::Result<()> {
+    execute!(stdout, Clear(ClearType::All), MoveTo(0, 0), Print("Select a saved game:"))?;
+    for (index, (path, saved)) in saves.iter().enumerate() {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let description = match saved {
+            Ok(saved) => format!(
+                "{} vs. {}, {} moves",
+                describe_player(saved.black),
+                describe_player(saved.white),
+                saved.game.history().len()
+            ),
+            Err(_) => "unreadable".to_string(),
+        };
+        execute!(
+            stdout,
+            MoveTo(2, index as u16 + 2),
+            Print(format!("{}. {} - {}", index + 1, name, description))
+        )?;
+    }
+    let bottom = saves.len() as u16 + 3;
+    execute!(
+        stdout,
+        MoveTo(0, bottom),
+        Print("Press 'b' to go back."),
+        MoveTo(0, bottom + 2),
+        SetForegroundColor(Color::Red),
+        Print(error),
+        ResetColor
+    )
+}
+
+fn draw_status(stdout: &mut Stdout, status: &str) -> std::io::Result<()> {
+    execute!(
+        stdout,
+        MoveTo(0, 13),
+        Clear(ClearType::CurrentLine),
+        SetForegroundColor(Color::Yellow),
+        Print(status),
+        ResetColor
+    )
+}
+
+fn draw_cpu_level_selection(stdout: &mut Stdout, title: &str) -> std::io::Result<()> {
     execute!(
         stdout,
         Clear(ClearType::All),
         MoveTo(0, 0),
-        Print("Select CPU difficulty:"),
+        Print(title),
         MoveTo(0, 2),
         Print("1. Easy - Random moves"),
         MoveTo(0, 3),
@@ -256,17 +684,26 @@ fn draw_cpu_level_selection(stdout: &mut Stdout, error: &str) -> std::io::Result
         MoveTo(0, 4),
         Print("3. Hard - Minimax algorithm"),
         MoveTo(0, 6),
-        Print("Press 'b' to go back, 'Ctrl+Q' to quit."),
-        MoveTo(0, 8),
-        SetForegroundColor(Color::Red),
-        Print(error),
-        ResetColor
+        Print("Press 'b' to go back, 'Ctrl+Q' to quit.")
     )
 }
 
-fn draw_game_over(stdout: &mut Stdout, game: &Game) -> std::io::Result<()> {
-    let (black_count, white_count) = game.count_discs();
-    
+fn draw_side_selection(stdout: &mut Stdout) -> std::io::Result<()> {
+    execute!(
+        stdout,
+        Clear(ClearType::All),
+        MoveTo(0, 0),
+        Print("Play as:"),
+        MoveTo(0, 2),
+        Print("1. Black (◯) - moves first"),
+        MoveTo(0, 3),
+        Print("2. White (●)"),
+        MoveTo(0, 5),
+        Print("Press 'b' to go back, 'Ctrl+Q' to quit.")
+    )
+}
+
+fn draw_game_over(stdout: &mut Stdout, result: &GameResult, transcript: &str) -> std::io::Result<()> {
     execute!(
         stdout,
         MoveTo(0, 14),
@@ -274,69 +711,270 @@ fn draw_game_over(stdout: &mut Stdout, game: &Game) -> std::io::Result<()> {
         MoveTo(0, 15),
     )?;
 
-    if black_count > white_count {
-        execute!(
-            stdout,
-            SetForegroundColor(Color::White),
-            Print("◯"),
-            ResetColor,
-            Print(" wins!")
-        )?;
-    } else if white_count > black_count {
-        execute!(
+    match result.winner {
+        Some(winner) => execute!(
             stdout,
             SetForegroundColor(Color::White),
-            Print("●"),
+            Print(if winner == Disc::Black { "◯" } else { "●" }),
             ResetColor,
-            Print(" wins!")
-        )?;
-    } else {
-        execute!(stdout, Print("It's a draw!"))?;
+            Print(format!(" wins {}-{}!", result.black.max(result.white), result.black.min(result.white)))
+        )?,
+        None => execute!(stdout, Print("It's a draw!"))?,
     }
 
     execute!(
         stdout,
         MoveTo(0, 16),
-        Print("Press 'Ctrl+Q' to exit.")
-    )?;
+        Print(format!("Moves: {}", transcript)),
+        MoveTo(0, 18),
+        Print("Press 'v' to replay the game, 'Ctrl+S' to save, 'Ctrl+Q' to exit.")
+    )
+}
 
+// Off the board, so `draw_board` highlights no square.
+const NO_CURSOR: (u16, u16) = (8, 8);
+// Beside the board, clear of the info lines below it.
+const MOVE_LIST_ROWS: usize = 8;
+
+// Steps through a game record until the viewer leaves with 'q' or Esc.
+fn run_replay(stdout: &mut Stdout, replay: &mut Replay, evaluator: &dyn Evaluator) -> std::io::Result<()> {
+    let mut analysis = None;
     loop {
+        let last_move = match replay.last_move().map(|record| record.mv) {
+            Some(Move::Place(row, col)) => (row as u16, col as u16),
+            _ => NO_CURSOR,
+        };
+        draw_board(stdout, replay.game().board(), last_move)?;
+        draw_info(
+            stdout,
+            replay.game(),
+            "Left/Right to step, Home/End for start/end, 'g' to go to a move, 'a' to analyze, 'q' to leave.",
+        )?;
+        draw_move_list(stdout, replay, analysis.as_ref())?;
+        if let Some(analysis) = &analysis {
+            draw_analysis(stdout, replay, analysis)?;
+        }
         if let Event::Key(key_event) = event::read()? {
-            if key_event.code == KeyCode::Char('q') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
-                break;
+            match key_event.code {
+                KeyCode::Right | KeyCode::Char('n') | KeyCode::Char(' ') => {
+                    replay.forward();
+                }
+                KeyCode::Left | KeyCode::Char('p') | KeyCode::Backspace => {
+                    replay.back();
+                }
+                KeyCode::Home => replay.jump(0),
+                KeyCode::End => replay.jump(replay.plies()),
+                KeyCode::Char('g') => {
+                    if let Some(ply) = read_move_number(stdout, replay.plies())? {
+                        replay.jump(ply);
+                    }
+                }
+                KeyCode::Char('a') if analysis.is_none() => {
+                    analysis = analyze_record(stdout, replay, evaluator)?;
+                }
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                _ => {}
             }
         }
     }
-    Ok(())
 }
 
-fn show_cpu_thinking(stdout: &mut Stdout, _game: &Game, level: CpuLevel) -> std::io::Result<()> {
-    let (thinking_time, frames) = match level {
-        CpuLevel::Easy => (800, vec!["CPU is thinking.", "CPU is thinking..", "CPU is thinking..."]),
-        CpuLevel::Medium => (1200, vec!["CPU is analyzing.", "CPU is analyzing..", "CPU is analyzing..."]),
-        CpuLevel::Hard => (2000, vec!["CPU is calculating.", "CPU is calculating..", "CPU is calculating..."]),
+const ANALYSIS_FRAME: Duration = Duration::from_millis(100);
+
+enum AnalysisUpdate {
+    Progress(usize, usize),
+    Done(GameAnalysis),
+}
+
+// Analyzes the whole record on a worker thread so the screen keeps updating.
+// Esc gives up and returns `None`; the worker stops after the move it is on.
+fn analyze_record(
+    stdout: &mut Stdout,
+    replay: &Replay,
+    evaluator: &dyn Evaluator,
+) -> std::io::Result<Option<GameAnalysis>> {
+    let mut whole = replay.clone();
+    whole.jump(whole.plies());
+    let game = whole.game();
+    let limits = SearchLimits::depth(DEFAULT_ANALYSIS_DEPTH);
+    let (sender, receiver) = mpsc::channel();
+    let cancelled = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let cancelled = &cancelled;
+        scope.spawn(move || {
+            let analysis = analysis::analyze(game, &limits, evaluator, |done, total| {
+                let _ = sender.send(AnalysisUpdate::Progress(done, total));
+                !cancelled.load(Ordering::Relaxed)
+            });
+            let _ = sender.send(AnalysisUpdate::Done(analysis));
+        });
+        draw_status(stdout, "Analyzing... (Esc to stop)")?;
+        loop {
+            while let Ok(update) = receiver.try_recv() {
+                match update {
+                    AnalysisUpdate::Progress(done, total) => draw_status(
+                        stdout,
+                        &format!("Analyzing move {} of {}... (Esc to stop)", done, total),
+                    )?,
+                    AnalysisUpdate::Done(analysis) => return Ok(Some(analysis)),
+                }
+            }
+            if event::poll(ANALYSIS_FRAME)? {
+                if let Event::Key(key_event) = event::read()? {
+                    if key_event.code == KeyCode::Esc {
+                        // The scope waits for the worker, which stops once the move it is on is done.
+                        cancelled.store(true, Ordering::Relaxed);
+                        draw_status(stdout, "Stopping the analysis...")?;
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    })
+}
+
+const GRAPH_HEIGHT: usize = 3;
+
+// The analysis of the move that led to the viewed position, and below it
+// Black's expected margin over the game with a marker under that move.
+fn draw_analysis(stdout: &mut Stdout, replay: &Replay, analysis: &GameAnalysis) -> std::io::Result<()> {
+    let current = replay.ply().checked_sub(1).and_then(|ply| analysis.at_ply(ply));
+    let text = match current {
+        Some(current) if current.loss() > 0.0 => format!(
+            "{} scores {:+.1}; best was {} {:+.1} ({:.1} discs lost)",
+            current.played,
+            current.played_score,
+            current.best,
+            current.best_score,
+            current.loss()
+        ),
+        Some(current) => format!("{} scores {:+.1}, the best move", current.played, current.played_score),
+        None => String::new(),
     };
-    
-    let frame_duration = thinking_time / frames.len() as u64;
-    
-    for frame in frames {
+    execute!(stdout, MoveTo(0, 14), Print(text))?;
+
+    let (rows, scale) = analysis::score_graph(&analysis.black_scores(), GRAPH_HEIGHT);
+    for (index, row) in rows.iter().enumerate() {
+        let label = match index {
+            0 => format!("{:+}", scale),
+            GRAPH_HEIGHT => "0".to_string(),
+            _ if index == rows.len() - 1 => format!("{:+}", -scale),
+            _ => String::new(),
+        };
+        execute!(stdout, MoveTo(0, 15 + index as u16), Print(format!("{:>4} {}", label, row)))?;
+    }
+    // Analyzed moves leave out passes, so the marker goes under the last one played.
+    let marker = analysis.moves.iter().take_while(|analysis| analysis.ply < replay.ply()).count();
+    if marker > 0 {
         execute!(
             stdout,
-            MoveTo(0, 13),
-            Clear(ClearType::CurrentLine),
+            MoveTo(4 + marker as u16, 15 + rows.len() as u16),
             SetForegroundColor(Color::Yellow),
-            Print(frame),
+            Print("^"),
             ResetColor
         )?;
-        thread::sleep(Duration::from_millis(frame_duration));
     }
-    
-    // Clear the thinking message
+    Ok(())
+}
+
+// Prompts on the status line for a move number; `Ok(None)` if the viewer cancels.
+fn read_move_number(stdout: &mut Stdout, plies: usize) -> std::io::Result<Option<usize>> {
+    let mut digits = String::new();
+    loop {
+        draw_status(stdout, &format!("Go to move (0-{}): {}", plies, digits))?;
+        if let Event::Key(key_event) = event::read()? {
+            match key_event.code {
+                KeyCode::Char(c) if c.is_ascii_digit() && digits.len() < 3 => digits.push(c),
+                KeyCode::Backspace => {
+                    digits.pop();
+                }
+                KeyCode::Enter => return Ok(digits.parse().ok()),
+                KeyCode::Esc => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+// The moves around the viewed one, to the right of the board, with the disc
+// counts after each.
+fn draw_move_list(stdout: &mut Stdout, replay: &Replay, analysis: Option<&GameAnalysis>) -> std::io::Result<()> {
+    let last_first = (replay.plies() + 1).saturating_sub(MOVE_LIST_ROWS);
+    let first = replay.ply().saturating_sub(MOVE_LIST_ROWS / 2).min(last_first);
     execute!(
         stdout,
-        MoveTo(0, 13),
-        Clear(ClearType::CurrentLine)
+        MoveTo(22, 0),
+        Print(format!("Move {} of {}", replay.ply(), replay.plies()))
     )?;
-    
+    for (row, ply) in (first..=replay.plies()).take(MOVE_LIST_ROWS).enumerate() {
+        let (black, white) = replay.counts(ply);
+        let text = match ply.checked_sub(1).map(|index| replay.moves()[index]) {
+            Some(record) => {
+                let symbol = if record.player == Disc::Black { "◯" } else { "●" };
+                let mv = if record.mv == Move::Pass { "pass".to_string() } else { record.mv.to_string() };
+                let judgement = analysis.and_then(|analysis| analysis.at_ply(ply - 1)).map(MoveAnalysis::judgement);
+                let mv = format!("{}{}", mv, judgement.map_or("", Judgement::symbol));
+                format!("{:>3}. {} {:<6} {:>2}-{:<2}", ply, symbol, mv, black, white)
+            }
+            None => format!("{:>3}. start    {:>2}-{:<2}", ply, black, white),
+        };
+        let color = if ply == replay.ply() { Color::Yellow } else { Color::Reset };
+        execute!(
+            stdout,
+            MoveTo(22, row as u16 + 1),
+            SetForegroundColor(color),
+            Print(text),
+            ResetColor
+        )?;
+    }
     Ok(())
 }
+
+const THINKING_FRAME: Duration = Duration::from_millis(250);
+// Instant replies (Easy/Medium) stay on screen long enough to follow.
+const MIN_THINKING_TIME: Duration = Duration::from_millis(750);
+
+fn show_cpu_thinking(
+    stdout: &mut Stdout,
+    game: &Game,
+    player: &mut dyn Player,
+    clock: &Clock,
+    min_time: Duration,
+) -> std::io::Result<(Move, Duration)> {
+    let name = player.name();
+    let label = format!("{} is thinking", name);
+    let started = Instant::now();
+
+    thread::scope(|scope| {
+        // Only the search counts against the clock, not the time the message stays up.
+        let search = scope.spawn(move || {
+            let started = Instant::now();
+            (player.choose_move(game, clock), started.elapsed())
+        });
+        let mut dots = 0;
+        while !search.is_finished() || started.elapsed() < min_time {
+            dots = dots % 3 + 1;
+            execute!(
+                stdout,
+                MoveTo(0, 13),
+                Clear(ClearType::CurrentLine),
+                SetForegroundColor(Color::Yellow),
+                Print(format!("{}{}", label, ".".repeat(dots))),
+                ResetColor
+            )?;
+            thread::sleep(THINKING_FRAME);
+        }
+
+        // Clear the thinking message
+        execute!(
+            stdout,
+            MoveTo(0, 13),
+            Clear(ClearType::CurrentLine)
+        )?;
+
+        let (mv, elapsed) = search.join().expect("CPU search panicked");
+        let mv = mv.unwrap_or_else(|| panic!("{} found no move in a game that is not over", name));
+        Ok((mv, elapsed))
+    })
+}
@@ -0,0 +1,226 @@
+//! The NBoard engine protocol, so the CPU can be used from NBoard and other
+//! Othello GUIs.
+//!
+//! The GUI sends one command per line; the engine answers only the commands that
+//! need an answer:
+//!
+//! ```text
+//! nboard 2                    -> set myname gemini-claude-othello
+//! set depth 12
+//! set game (;GM[Othello]...;)
+//! move F5/0.00/1.2
+//! hint 3                      -> status thinking
+//!                                search D6 -1.20 0 11
+//!                                ...
+//!                                status
+//! go                          -> === F4/1.50/2.01
+//! ping 4                      -> pong 4
+//! learn                       -> learned
+//! ```
+//!
+//! Evaluations are final disc margins from the point of view of the side to move.
+
+use crate::cpu::{self, CpuConfig, SearchLimits};
+use crate::game::{Game, Move};
+use crate::ggf::{format_move, GgfGame};
+use crate::tt::TranspositionTable;
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+pub const ENGINE_NAME: &str = "gemini-claude-othello";
+pub const DEFAULT_DEPTH: u32 = 20;
+
+pub struct NboardEngine {
+    game: Game,
+    depth: u32,
+    config: CpuConfig,
+    table: TranspositionTable,
+}
+
+impl NboardEngine {
+    /// `config.limits` caps every search; `set depth` only lowers the depth.
+    pub fn new(config: CpuConfig) -> Self {
+        let table = TranspositionTable::new(config.tt_size_mb);
+        NboardEngine { game: Game::new(), depth: DEFAULT_DEPTH, config, table }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Handles one command line, writing any replies to `out`. Unknown commands are
+    /// ignored, as the protocol asks; malformed ones are reported as a status line.
+    /// Returns `Ok(false)` on `quit`.
+    pub fn handle<W: Write>(&mut self, line: &str, out: &mut W) -> io::Result<bool> {
+        let (command, args) = match line.trim().split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (line.trim(), ""),
+        };
+        let result = match command {
+            "nboard" => writeln!(out, "set myname {}", ENGINE_NAME).map(Ok),
+            "set" => Ok(self.set(args)),
+            "move" => Ok(self.play(args)),
+            "hint" => self.hint(args, out),
+            "go" => self.go(out).map(Ok),
+            "ping" => writeln!(out, "pong {}", args).map(Ok),
+            "learn" => writeln!(out, "learned").map(Ok),
+            "quit" => return Ok(false),
+            _ => Ok(Ok(())),
+        }?;
+        if let Err(message) = result {
+            writeln!(out, "status {}", message)?;
+        }
+        out.flush()?;
+        Ok(true)
+    }
+
+    fn set(&mut self, args: &str) -> Result<(), String> {
+        let (name, value) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        match name {
+            "depth" => {
+                let depth: u32 = value.trim().parse().map_err(|_| format!("invalid depth '{}'", value.trim()))?;
+                self.depth = depth.clamp(1, 60);
+            }
+            "game" => self.game = GgfGame::parse(value).map_err(|e| e.to_string())?.game,
+            // Contempt and other settings do not apply to this engine.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, args: &str) -> Result<(), String> {
+        let text = args.split('/').next().unwrap_or_default().trim();
+        let mv: Move = text.parse().map_err(|_| format!("malformed move '{}'", text))?;
+        if self.game.play(mv) {
+            Ok(())
+        } else {
+            Err(format!("illegal move '{}'", text))
+        }
+    }
+
+    fn limits(&self) -> SearchLimits {
+        SearchLimits { max_depth: self.depth.min(self.config.limits.max_depth), ..self.config.limits }
+    }
+
+    fn go<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let start = Instant::now();
+        let player = self.game.current_turn();
+        if self.game.is_game_over() || self.game.must_pass() {
+            return writeln!(out, "=== PA");
+        }
+        if let Some((row, col)) = self.config.book_move(&self.game, player) {
+            return writeln!(out, "=== {}", format_move(Move::Place(row, col)));
+        }
+        writeln!(out, "status thinking")?;
+        out.flush()?;
+        let limits = self.limits();
+        let evaluator = self.config.evaluator.as_ref();
+        let result =
+            cpu::search_with_table(&self.game, player, &limits, evaluator, &mut self.table).expect("side to move has a move");
+        let (row, col) = result.best_move;
+        writeln!(
+            out,
+            "=== {}/{:.2}/{:.2}",
+            format_move(Move::Place(row, col)),
+            result.disc_margin(),
+            start.elapsed().as_secs_f64()
+        )
+    }
+
+    fn hint<W: Write>(&mut self, args: &str, out: &mut W) -> io::Result<Result<(), String>> {
+        let count: usize = match args.parse() {
+            Ok(count) => count,
+            Err(_) => return Ok(Err(format!("invalid hint count '{}'", args))),
+        };
+        if self.game.is_game_over() {
+            return Ok(Ok(()));
+        }
+        writeln!(out, "status thinking")?;
+        out.flush()?;
+        if self.game.must_pass() {
+            writeln!(out, "search PA 0.00 0 0")?;
+        } else {
+            let limits = self.limits();
+            let ranked = cpu::rank_moves(&self.game, &limits, self.config.evaluator.as_ref(), &mut self.table);
+            for result in ranked.into_iter().take(count) {
+                let (row, col) = result.best_move;
+                let depth = if result.is_exact() { "100%".to_string() } else { result.depth.to_string() };
+                writeln!(
+                    out,
+                    "search {} {:.2} 0 {}",
+                    format_move(Move::Place(row, col)),
+                    result.disc_margin(),
+                    depth
+                )?;
+            }
+        }
+        writeln!(out, "status").map(Ok)
+    }
+}
+
+/// Reads commands from `input` until it ends or `quit` arrives.
+pub fn run<R: BufRead, W: Write>(input: R, mut output: W, config: CpuConfig) -> io::Result<()> {
+    let mut engine = NboardEngine::new(config);
+    for line in input.lines() {
+        if !engine.handle(&line?, &mut output)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "(;GM[Othello]PC[NBoard]PB[a]PW[b]RE[?]TI[5:00]TY[8]BO[8 ---------------------------O*------*O--------------------------- *]B[F5]W[D6//1.2];)";
+
+    fn talk(engine: &mut NboardEngine, commands: &[&str]) -> String {
+        let mut out = Vec::new();
+        for command in commands {
+            engine.handle(command, &mut out).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    fn engine() -> NboardEngine {
+        NboardEngine::new(CpuConfig { limits: SearchLimits::depth(3), book: None, seed: Some(1), ..CpuConfig::default() })
+    }
+
+    #[test]
+    fn test_handshake_and_ping() {
+        let mut engine = engine();
+        assert_eq!(talk(&mut engine, &["nboard 2", "set depth 4", "ping 1"]), "set myname gemini-claude-othello\npong 1\n");
+    }
+
+    #[test]
+    fn test_set_game_and_move() {
+        let mut engine = engine();
+        assert_eq!(talk(&mut engine, &[&format!("set game {}", START), "move C3/0.5/1.0"]), "");
+        assert_eq!(engine.game().transcript(), "f5d6c3");
+
+        let reply = talk(&mut engine, &["move A1"]);
+        assert_eq!(reply, "status illegal move 'A1'\n");
+        assert_eq!(engine.game().transcript(), "f5d6c3");
+    }
+
+    #[test]
+    fn test_go() {
+        let mut engine = engine();
+        let reply = talk(&mut engine, &["go"]);
+        let answer = reply.lines().last().unwrap();
+        let mv = answer.strip_prefix("=== ").unwrap().split('/').next().unwrap();
+        assert!(["F5", "E6", "D3", "C4"].contains(&mv), "{}", reply);
+    }
+
+    #[test]
+    fn test_hint() {
+        let mut engine = engine();
+        let reply = talk(&mut engine, &["move f5", "hint 2"]);
+        let lines: Vec<&str> = reply.lines().collect();
+        assert_eq!(lines.first(), Some(&"status thinking"));
+        assert_eq!(lines.last(), Some(&"status"));
+        let searches: Vec<&str> = lines.iter().filter(|line| line.starts_with("search ")).copied().collect();
+        assert_eq!(searches.len(), 2);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::board::{Board, Disc};
+use std::error::Error;
+use std::fmt;
+
+/// Why a move transcript could not be read or replayed. `ply` counts moves in
+/// the transcript from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptError {
+    Malformed { ply: usize, text: String },
+    IllegalMove { ply: usize, text: String },
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranscriptError::Malformed { ply, text } => write!(f, "malformed move '{}' at ply {}", text, ply),
+            TranscriptError::IllegalMove { ply, text } => write!(f, "illegal move {} at ply {}", text, ply),
+        }
+    }
+}
+
+impl Error for TranscriptError {}
+
+/// Why a position string could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    /// The board part did not have exactly 64 squares.
+    WrongLength(usize),
+    InvalidSquare { index: usize, found: char },
+    InvalidSide(String),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::WrongLength(len) => write!(f, "expected 64 squares, found {}", len),
+            PositionError::InvalidSquare { index, found } => {
+                write!(f, "invalid square '{}' at {}", found, format_square(index / 8, index % 8))
+            }
+            PositionError::InvalidSide(side) => write!(f, "invalid side to move '{}'", side),
+        }
+    }
+}
+
+impl Error for PositionError {}
+
+/// Parses a position string: 64 squares in row-major order from a1 to h8 (`X` black,
+/// `O` white, `-` empty), whitespace, then the side to move (`X` or `O`). This is the
+/// "obf" format used by endgame test suites; a trailing `;` and anything after it
+/// (such as expected scores) are ignored.
+pub fn parse_position(text: &str) -> Result<(Board, Disc), PositionError> {
+    let text = text.split(';').next().unwrap().trim();
+    let mut fields = text.split_whitespace();
+    let squares = fields.next().unwrap_or("");
+    let side = fields.next().unwrap_or("");
+
+    let count = squares.chars().count();
+    if count != 64 {
+        return Err(PositionError::WrongLength(count));
+    }
+    let mut board = Board::empty();
+    for (index, found) in squares.chars().enumerate() {
+        let disc = match found {
+            'X' | 'x' | '*' => Disc::Black,
+            'O' | 'o' => Disc::White,
+            '-' | '.' => Disc::Empty,
+            _ => return Err(PositionError::InvalidSquare { index, found }),
+        };
+        board.put_disc(index / 8, index % 8, disc);
+    }
+
+    let side = match side {
+        "X" | "x" | "*" | "B" | "b" => Disc::Black,
+        "O" | "o" | "W" | "w" => Disc::White,
+        other => return Err(PositionError::InvalidSide(other.to_string())),
+    };
+    Ok((board, side))
+}
+
+/// Formats a position in the format read by [`parse_position`].
+pub fn format_position(board: &Board, side_to_move: Disc) -> String {
+    let mut text = String::with_capacity(66);
+    for row in 0..8 {
+        for col in 0..8 {
+            text.push(match board.get_disc(row, col) {
+                Some(Disc::Black) => 'X',
+                Some(Disc::White) => 'O',
+                _ => '-',
+            });
+        }
+    }
+    text.push(' ');
+    text.push(if side_to_move == Disc::White { 'O' } else { 'X' });
+    text
+}
+
+/// Splits a transcript into two-character move tokens, ignoring whitespace.
+pub fn tokenize(transcript: &str) -> Vec<String> {
+    let compact: Vec<char> = transcript.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.chunks(2).map(|pair| pair.iter().collect()).collect()
+}
+
+/// Formats (row, col) in standard Othello notation, e.g. (4, 5) -> "f5".
+pub fn format_square(row: usize, col: usize) -> String {
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+
+/// Parses a square such as "f5" or "F5" into (row, col).
+pub fn parse_square(text: &str) -> Option<(usize, usize)> {
+    let mut chars = text.chars();
+    let (file, rank) = (chars.next()?, chars.next()?);
+    if chars.next().is_some() {
+        return None;
+    }
+    let col = match file.to_ascii_lowercase() {
+        c @ 'a'..='h' => c as usize - 'a' as usize,
+        _ => return None,
+    };
+    let row = match rank {
+        r @ '1'..='8' => r as usize - '1' as usize,
+        _ => return None,
+    };
+    Some((row, col))
+}
+
+/// Parses a concatenated transcript such as "f5d6c3" into squares. Whitespace is
+/// ignored. Returns `None` if any square is malformed.
+pub fn parse_squares(transcript: &str) -> Option<Vec<(usize, usize)>> {
+    tokenize(transcript).iter().map(|token| parse_square(token)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_square() {
+        assert_eq!(format_square(0, 0), "a1");
+        assert_eq!(format_square(4, 5), "f5");
+        assert_eq!(format_square(7, 7), "h8");
+    }
+
+    #[test]
+    fn test_parse_square() {
+        assert_eq!(parse_square("a1"), Some((0, 0)));
+        assert_eq!(parse_square("F5"), Some((4, 5)));
+        assert_eq!(parse_square("h8"), Some((7, 7)));
+        assert_eq!(parse_square("i1"), None);
+        assert_eq!(parse_square("a9"), None);
+        assert_eq!(parse_square("a"), None);
+        assert_eq!(parse_square("a10"), None);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("f5d6 c3\n"), vec!["f5", "d6", "c3"]);
+        assert_eq!(tokenize("f5d"), vec!["f5", "d"]);
+    }
+
+    #[test]
+    fn test_transcript_error_display() {
+        let error = TranscriptError::IllegalMove { ply: 3, text: "a1".to_string() };
+        assert_eq!(error.to_string(), "illegal move a1 at ply 3");
+        let error = TranscriptError::Malformed { ply: 1, text: "z9".to_string() };
+        assert_eq!(error.to_string(), "malformed move 'z9' at ply 1");
+    }
+
+    const START: &str = "---------------------------OX------XO--------------------------- X";
+
+    #[test]
+    fn test_parse_position() {
+        let (board, side) = parse_position(START).unwrap();
+        assert_eq!(board, Board::new());
+        assert_eq!(side, Disc::Black);
+
+        // Test suites append the expected result after a semicolon.
+        let text = "---------------------------OO------OO--------------------------- O; e6:+4";
+        let (board, side) = parse_position(text).unwrap();
+        assert_eq!(board.count(Disc::White), 4);
+        assert_eq!(side, Disc::White);
+    }
+
+    #[test]
+    fn test_position_round_trip() {
+        let mut board = Board::new();
+        board.apply_move(4, 5, Disc::Black);
+        let text = format_position(&board, Disc::White);
+        assert_eq!(parse_position(&text), Ok((board, Disc::White)));
+        assert_eq!(format_position(&Board::new(), Disc::Black), START);
+    }
+
+    #[test]
+    fn test_parse_position_errors() {
+        assert_eq!(parse_position("XO- X"), Err(PositionError::WrongLength(3)));
+        let bad = format!("{}Z{} X", "-".repeat(10), "-".repeat(53));
+        assert_eq!(parse_position(&bad), Err(PositionError::InvalidSquare { index: 10, found: 'Z' }));
+        assert_eq!(parse_position(&bad).unwrap_err().to_string(), "invalid square 'Z' at c2");
+        let no_side = format!("{} ?", "-".repeat(64));
+        assert_eq!(parse_position(&no_side), Err(PositionError::InvalidSide("?".to_string())));
+    }
+
+    #[test]
+    fn test_parse_squares() {
+        assert_eq!(parse_squares("f5d6 C3"), Some(vec![(4, 5), (5, 3), (2, 2)]));
+        assert_eq!(parse_squares(""), Some(vec![]));
+        assert_eq!(parse_squares("f5d"), None);
+        assert_eq!(parse_squares("f5z9"), None);
+    }
+}
@@ -0,0 +1,417 @@
+//! Pattern-based evaluation and its offline training.
+//!
+//! The evaluation adds up one learned weight for the configuration of each
+//! pattern instance (edges, corner regions and diagonals in all their
+//! orientations) plus a parity term, with separate weights for each game phase.
+//! Weights are fitted by stochastic gradient descent to the final margins of
+//! positions from a game database.
+
+use crate::board::{Board, Disc};
+use crate::book::transform_square;
+use crate::cpu::EVAL_PER_DISC;
+use crate::eval::Evaluator;
+use crate::game::{Game, Move};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Weights are kept separately for every ten empty squares.
+pub const PHASES: usize = 6;
+
+/// First line of a pattern weights file, which tells it apart from a feature
+/// weights file.
+pub const HEADER: &str = "pattern-weights";
+
+struct Pattern {
+    name: &'static str,
+    squares: &'static [(usize, usize)],
+}
+
+const PATTERNS: [Pattern; 8] = [
+    Pattern { name: "edge", squares: &[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (0, 6), (0, 7)] },
+    Pattern {
+        name: "corner3x3",
+        squares: &[(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)],
+    },
+    Pattern {
+        name: "corner2x5",
+        squares: &[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (1, 0), (1, 1), (1, 2), (1, 3), (1, 4)],
+    },
+    Pattern { name: "diag8", squares: &[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6), (7, 7)] },
+    Pattern { name: "diag7", squares: &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7)] },
+    Pattern { name: "diag6", squares: &[(0, 2), (1, 3), (2, 4), (3, 5), (4, 6), (5, 7)] },
+    Pattern { name: "diag5", squares: &[(0, 3), (1, 4), (2, 5), (3, 6), (4, 7)] },
+    Pattern { name: "diag4", squares: &[(0, 4), (1, 5), (2, 6), (3, 7)] },
+];
+
+// One placement of a pattern on the board.
+struct Instance {
+    // Index of the pattern's first weight within a phase's table.
+    offset: usize,
+    squares: Vec<u64>,
+}
+
+struct Layout {
+    instances: Vec<Instance>,
+    // Offset of each pattern's weights, and the total table size per phase.
+    offsets: Vec<usize>,
+    size: usize,
+}
+
+fn layout() -> &'static Layout {
+    static LAYOUT: OnceLock<Layout> = OnceLock::new();
+    LAYOUT.get_or_init(|| {
+        let mut instances = Vec::new();
+        let mut offsets = Vec::new();
+        let mut size = 0;
+        for pattern in &PATTERNS {
+            offsets.push(size);
+            // All eight orientations, even when two cover the same squares (such as
+            // an edge read from either end), so that the evaluation is symmetric.
+            for transform in 0..8 {
+                let squares: Vec<u64> = pattern
+                    .squares
+                    .iter()
+                    .map(|&(row, col)| {
+                        let (row, col) = transform_square(transform, row, col);
+                        1u64 << (row * 8 + col)
+                    })
+                    .collect();
+                instances.push(Instance { offset: size, squares });
+            }
+            size += 3usize.pow(pattern.squares.len() as u32);
+        }
+        Layout { instances, offsets, size }
+    })
+}
+
+fn phase(empties: u32) -> usize {
+    (empties.min(59) / 10) as usize
+}
+
+// Ternary configuration of every instance for `player` to move: empty 0, own 1,
+// opponent 2, with the pattern's first square as the least significant digit.
+fn features(board: &Board, player: Disc) -> impl Iterator<Item = usize> + '_ {
+    let own = board.bitboard(player);
+    let opponent = board.bitboard(player.opponent());
+    layout().instances.iter().map(move |instance| {
+        let config = instance.squares.iter().rev().fold(0, |acc, &bit| {
+            let digit = if own & bit != 0 {
+                1
+            } else if opponent & bit != 0 {
+                2
+            } else {
+                0
+            };
+            acc * 3 + digit
+        });
+        instance.offset + config
+    })
+}
+
+fn parity(board: &Board) -> f32 {
+    if board.count(Disc::Empty) % 2 == 1 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// Learned weights for the pattern evaluation, in evaluation points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternWeights {
+    tables: Vec<Vec<f32>>,
+    parity: [f32; PHASES],
+}
+
+impl Default for PatternWeights {
+    fn default() -> Self {
+        PatternWeights { tables: vec![vec![0.0; layout().size]; PHASES], parity: [0.0; PHASES] }
+    }
+}
+
+impl PatternWeights {
+    /// All weights zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn score(&self, board: &Board, player: Disc) -> f32 {
+        let phase = phase(board.count(Disc::Empty));
+        let table = &self.tables[phase];
+        features(board, player).map(|index| table[index]).sum::<f32>() + self.parity[phase] * parity(board)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<PatternWeights> {
+        PatternWeights::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the format written by [`PatternWeights::to_text`]: a `pattern-weights`
+    /// header, then `parity PHASE VALUE` and `PATTERN PHASE CONFIG VALUE` lines,
+    /// where CONFIG lists the pattern's squares as `X` (side to move), `O` or `-`.
+    /// Configurations that are not listed weigh 0.
+    pub fn parse(text: &str) -> io::Result<PatternWeights> {
+        let mut weights = PatternWeights::new();
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty());
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(invalid_data(format!("missing '{}' header", HEADER)));
+        }
+        for (number, line) in lines {
+            let error = |message: &str| invalid_data(format!("line {}: {}", number, message));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let phase = |text: &str| {
+                text.parse::<usize>().ok().filter(|&phase| phase < PHASES).ok_or_else(|| error("invalid phase"))
+            };
+            let value = |text: &str| {
+                text.parse::<f32>().ok().filter(|value| value.is_finite()).ok_or_else(|| error("invalid weight"))
+            };
+            match fields.as_slice() {
+                ["parity", p, v] => weights.parity[phase(p)?] = value(v)?,
+                [name, p, config, v] => {
+                    let pattern =
+                        PATTERNS.iter().position(|pattern| pattern.name == *name).ok_or_else(|| error("unknown pattern"))?;
+                    if config.len() != PATTERNS[pattern].squares.len() {
+                        return Err(error("configuration has the wrong length"));
+                    }
+                    let mut index = 0;
+                    for c in config.chars().rev() {
+                        let digit = match c {
+                            '-' => 0,
+                            'X' => 1,
+                            'O' => 2,
+                            _ => return Err(error("invalid configuration")),
+                        };
+                        index = index * 3 + digit;
+                    }
+                    weights.tables[phase(p)?][layout().offsets[pattern] + index] = value(v)?;
+                }
+                _ => return Err(error("expected 'PATTERN PHASE CONFIG VALUE'")),
+            }
+        }
+        Ok(weights)
+    }
+
+    /// The weights as text, leaving out those that round to zero.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n# Written by the train command.\n", HEADER);
+        for (phase, value) in self.parity.iter().enumerate() {
+            let _ = writeln!(text, "parity {} {:.2}", phase, value);
+        }
+        for (pattern, offset) in PATTERNS.iter().zip(&layout().offsets) {
+            let count = 3usize.pow(pattern.squares.len() as u32);
+            for (phase, table) in self.tables.iter().enumerate() {
+                for (index, value) in table[*offset..offset + count].iter().enumerate() {
+                    if value.abs() < 0.005 {
+                        continue;
+                    }
+                    let mut config = String::new();
+                    let mut rest = index;
+                    for _ in 0..pattern.squares.len() {
+                        config.push(['-', 'X', 'O'][rest % 3]);
+                        rest /= 3;
+                    }
+                    let _ = writeln!(text, "{} {} {} {:.2}", pattern.name, phase, config, value);
+                }
+            }
+        }
+        text
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+}
+
+impl Evaluator for PatternWeights {
+    fn evaluate(&self, board: &Board, player: Disc) -> i32 {
+        self.score(board, player).round() as i32
+    }
+}
+
+/// A position labelled with the final disc margin for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub board: Board,
+    pub player: Disc,
+    pub margin: i32,
+}
+
+/// Every position of a finished game where a move was played, labelled with the
+/// game's result. Returns nothing for unfinished games.
+pub fn samples_from_game(game: &Game) -> Vec<Sample> {
+    let Some(result) = game.result() else {
+        return Vec::new();
+    };
+    let (mut board, _) = game.start_position();
+    let mut samples = Vec::new();
+    for record in game.history() {
+        if let Move::Place(row, col) = record.mv {
+            samples.push(Sample { board, player: record.player, margin: result.margin(record.player) });
+            board.apply_move(row, col, record.player);
+        }
+    }
+    samples
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainOptions {
+    pub epochs: u32,
+    /// Fraction of a sample's error corrected by each update, from 0 to 1.
+    pub rate: f32,
+    /// Seed for the order samples are visited in.
+    pub seed: u64,
+}
+
+impl Default for TrainOptions {
+    fn default() -> Self {
+        TrainOptions { epochs: 20, rate: 0.1, seed: 0 }
+    }
+}
+
+/// Fits pattern weights to `samples` by stochastic gradient descent on the
+/// squared error, starting from `initial`. Each step is normalized by the
+/// sample's feature count, so a configuration that several instances share (such
+/// as an empty edge) does not make the update overshoot. `on_epoch` sees each epoch's number and
+/// the root-mean-square error in discs over its updates.
+pub fn train(
+    initial: PatternWeights,
+    samples: &[Sample],
+    options: &TrainOptions,
+    mut on_epoch: impl FnMut(u32, f64),
+) -> PatternWeights {
+    let mut weights = initial;
+    let per_sample = layout().instances.len();
+    // Feature indices are fixed, so work them out once.
+    let indices: Vec<u32> =
+        samples.iter().flat_map(|sample| features(&sample.board, sample.player).map(|index| index as u32)).collect();
+    // Squared norm of each sample's feature vector, counting repeats: how far one
+    // unit of step moves the prediction.
+    let norms: Vec<f32> = indices
+        .chunks(per_sample)
+        .map(|features| {
+            let mut sorted = features.to_vec();
+            sorted.sort_unstable();
+            let repeats = sorted.chunk_by(|a, b| a == b).map(|run| (run.len() * run.len()) as f32).sum::<f32>();
+            repeats + 1.0
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..samples.len()).collect();
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    for epoch in 1..=options.epochs {
+        order.shuffle(&mut rng);
+        let mut squared_error = 0.0f64;
+        for &i in &order {
+            let sample = &samples[i];
+            let phase = phase(sample.board.count(Disc::Empty));
+            let parity = parity(&sample.board);
+            let features = &indices[i * per_sample..(i + 1) * per_sample];
+            let table = &mut weights.tables[phase];
+            let predicted: f32 =
+                features.iter().map(|&index| table[index as usize]).sum::<f32>() + weights.parity[phase] * parity;
+            let error = sample.margin as f32 * EVAL_PER_DISC as f32 - predicted;
+            squared_error += (error as f64).powi(2);
+            let step = options.rate * error / norms[i];
+            for &index in features {
+                table[index as usize] += step;
+            }
+            weights.parity[phase] += step * parity;
+        }
+        let rmse = (squared_error / samples.len().max(1) as f64).sqrt() / EVAL_PER_DISC;
+        on_epoch(epoch, rmse);
+    }
+    weights
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::random_opening;
+    use rand::Rng;
+
+    #[test]
+    fn test_layout() {
+        let layout = layout();
+        assert_eq!(layout.instances.len(), 8 * PATTERNS.len());
+        assert_eq!(layout.size, 6561 + 19683 + 59049 + 6561 + 2187 + 729 + 243 + 81);
+        assert_eq!(phase(60), 5);
+        assert_eq!(phase(0), 0);
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut weights = PatternWeights::new();
+        weights.parity[2] = 1.5;
+        weights.tables[0][layout().offsets[0] + 1] = 30.0;
+        weights.tables[5][layout().offsets[7] + 80] = -4.25;
+        let text = weights.to_text();
+        assert!(text.contains("edge 0 X------- 30.00"), "{}", text);
+        assert_eq!(PatternWeights::parse(&text).unwrap(), weights);
+
+        assert!(PatternWeights::parse("edge 0 X------- 1").is_err());
+        assert!(PatternWeights::parse("pattern-weights\nedge 0 X 1").is_err());
+        assert!(PatternWeights::parse("pattern-weights\nedge 9 X------- 1").is_err());
+    }
+
+    #[test]
+    fn test_evaluation_is_symmetric() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut weights = PatternWeights::new();
+        for table in &mut weights.tables {
+            for value in table.iter_mut() {
+                *value = rng.random_range(-10.0..10.0);
+            }
+        }
+        // Every rotation and reflection of a board scores the same.
+        let game = random_opening(20, &mut rng);
+        let board = game.board();
+        let player = game.current_turn();
+        let expected = weights.score(board, player);
+        for transform in 1..8 {
+            let mut transformed = Board::empty();
+            for row in 0..8 {
+                for col in 0..8 {
+                    let (r, c) = transform_square(transform, row, col);
+                    transformed.put_disc(r, c, board.get_disc(row, col).unwrap());
+                }
+            }
+            assert!((weights.score(&transformed, player) - expected).abs() < 0.01, "transform {}", transform);
+        }
+    }
+
+    #[test]
+    fn test_training_reduces_error() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut samples = Vec::new();
+        for _ in 0..20 {
+            let game = random_opening(70, &mut rng);
+            samples.extend(samples_from_game(&game));
+        }
+        assert!(samples.len() > 20 * 50);
+        assert!(samples_from_game(&Game::from_transcript("f5d6").unwrap()).is_empty());
+
+        let mut errors = Vec::new();
+        let options = TrainOptions { epochs: 5, ..TrainOptions::default() };
+        let weights = train(PatternWeights::new(), &samples, &options, |_, rmse| errors.push(rmse));
+        assert_eq!(errors.len(), 5);
+        assert!(errors.windows(2).all(|pair| pair[1] < pair[0]), "{:?}", errors);
+        assert!(errors[4] < errors[0] * 0.75, "{:?}", errors);
+
+        let sample = samples[0];
+        let predicted = weights.evaluate(&sample.board, sample.player) as f64 / EVAL_PER_DISC;
+        assert!((predicted - sample.margin as f64).abs() < (sample.margin as f64).abs() + 10.0);
+    }
+}
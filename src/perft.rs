@@ -0,0 +1,106 @@
+//! Move generation counts ("perft") for validating the rules engine.
+//!
+//! `perft(depth)` counts the lines of exactly `depth` plies from a position. A
+//! forced pass counts as a ply, and a game that ends early counts as one line
+//! ending where it stopped, which is the convention behind the published counts.
+
+use crate::board::{squares, Board, Disc};
+use crate::game::{Game, Move};
+
+/// Node counts from the initial position, for depths 1 onwards.
+pub const INITIAL_COUNTS: [u64; 14] = [
+    4,
+    12,
+    56,
+    244,
+    1_396,
+    8_200,
+    55_092,
+    390_216,
+    3_005_288,
+    24_571_284,
+    212_258_800,
+    1_939_886_636,
+    18_429_641_748,
+    184_042_084_512,
+];
+
+/// The published count for `depth` plies from the initial position, if known.
+pub fn initial_count(depth: u32) -> Option<u64> {
+    INITIAL_COUNTS.get((depth as usize).checked_sub(1)?).copied()
+}
+
+/// Counts the lines of `depth` plies from the position in `game`.
+pub fn perft(game: &Game, depth: u32) -> u64 {
+    count(game.board(), game.current_turn(), depth, false)
+}
+
+/// The count below each move of the side to move, for narrowing down where two
+/// move generators disagree.
+pub fn divide(game: &Game, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    game.legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut next = game.clone();
+            next.play(mv);
+            (mv, perft(&next, depth - 1))
+        })
+        .collect()
+}
+
+fn count(board: &Board, player: Disc, depth: u32, passed: bool) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.legal_moves(player);
+    if moves == 0 {
+        // Two passes in a row end the game: the line stops here.
+        return if passed { 1 } else { count(board, player.opponent(), depth - 1, true) };
+    }
+    if depth == 1 {
+        return moves.count_ones() as u64;
+    }
+    squares(moves)
+        .map(|(row, col)| {
+            let mut next = *board;
+            next.apply_move(row, col, player);
+            count(&next, player.opponent(), depth - 1, false)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_counts() {
+        for depth in 1..=7 {
+            assert_eq!(Some(perft(&Game::new(), depth)), initial_count(depth), "depth {}", depth);
+        }
+        assert_eq!(perft(&Game::new(), 0), 1);
+        assert_eq!(initial_count(0), None);
+        assert_eq!(initial_count(15), None);
+    }
+
+    #[test]
+    fn test_divide() {
+        let game = Game::from_transcript("f5d6").unwrap();
+        let divided = divide(&game, 3);
+        assert_eq!(divided.len(), game.legal_moves().len());
+        assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), perft(&game, 3));
+    }
+
+    #[test]
+    fn test_passes_and_game_end() {
+        // Black must pass, then White's c1 fills the row and ends the game.
+        let game = Game::from_position_str(&format!("OX{} X", "-".repeat(62))).unwrap();
+        assert_eq!(perft(&game, 1), 1);
+        assert_eq!(perft(&game, 2), 1);
+        assert_eq!(perft(&game, 5), 1);
+        assert_eq!(divide(&game, 2), vec![(Move::Pass, 1)]);
+    }
+}
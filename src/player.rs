@@ -1,76 +1,190 @@
-use crate::game::Game;
 use crate::board::Disc;
+use crate::clock::Clock;
+use crate::cpu::CpuConfig;
+use crate::game::{Game, Move};
+use crate::tt::TranspositionTable;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CpuLevel {
     Easy,
     Medium,
     Hard,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlayerType {
     Human,
     Cpu(CpuLevel),
 }
 
-pub struct Player {
-    player_type: PlayerType,
-    disc: Disc,
+/// Anything that can pick moves on its own: the built-in CPU levels or a custom
+/// bot. Players may be moved to a worker thread while they think, so they must
+/// be `Send`.
+///
+/// ```
+/// use gemini_claude_othello::clock::Clock;
+/// use gemini_claude_othello::{Game, Move, Player};
+///
+/// /// Always plays the first legal move in row-major order.
+/// struct FirstMove;
+///
+/// impl Player for FirstMove {
+///     fn name(&self) -> String {
+///         "First move".to_string()
+///     }
+///
+///     fn choose_move(&mut self, game: &Game, _clock: &Clock) -> Option<Move> {
+///         game.legal_moves().first().copied()
+///     }
+/// }
+///
+/// let mut game = Game::new();
+/// let mv = FirstMove.choose_move(&game, &Clock::unlimited()).unwrap();
+/// assert!(game.play(mv));
+/// ```
+pub trait Player: Send {
+    fn name(&self) -> String;
+
+    /// Picks a move for the side to move in `game`, which is not over. Returns
+    /// `None` if the player cannot or will not move, e.g. a bot that resigns or
+    /// an engine that crashed.
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Option<Move>;
+
+    /// The evaluation behind the last move `choose_move` returned, as a final disc
+    /// margin for the side that played it. Players that do not evaluate return
+    /// `None`.
+    fn last_eval(&self) -> Option<f64> {
+        None
+    }
 }
 
-impl Player {
-    pub fn new(player_type: PlayerType, disc: Disc) -> Self {
-        Player { player_type, disc }
+/// One of the built-in CPU levels. The Hard level keeps its transposition
+/// table from move to move.
+#[derive(Debug, Clone)]
+pub struct CpuPlayer {
+    level: CpuLevel,
+    config: CpuConfig,
+    last_eval: Option<f64>,
+    // Allocated on the first Hard search.
+    table: Option<TranspositionTable>,
+}
+
+impl CpuPlayer {
+    pub fn new(level: CpuLevel) -> Self {
+        CpuPlayer { level, config: CpuConfig::default(), last_eval: None, table: None }
     }
 
-    pub fn player_type(&self) -> &PlayerType {
-        &self.player_type
+    /// Sets the book, search budget, seed and table size. A timed clock overrides
+    /// the search time.
+    pub fn with_config(mut self, config: CpuConfig) -> Self {
+        self.config = config;
+        self.table = None;
+        self
     }
 
-    pub fn get_move(&self, game: &Game) -> (usize, usize) {
-        match self.player_type {
-            PlayerType::Human => panic!("Human move should be handled in main loop"),
-            PlayerType::Cpu(level) => self.get_cpu_move(game, level),
-        }
+    pub fn level(&self) -> CpuLevel {
+        self.level
+    }
+}
+
+impl Player for CpuPlayer {
+    fn name(&self) -> String {
+        format!("CPU ({:?})", self.level)
     }
 
-    fn get_cpu_move(&self, game: &Game, level: CpuLevel) -> (usize, usize) {
-        crate::cpu::get_best_move(game, self.disc, level)
+    fn choose_move(&mut self, game: &Game, clock: &Clock) -> Option<Move> {
+        self.last_eval = None;
+        if game.is_game_over() {
+            return None;
+        }
+        if game.must_pass() {
+            return Some(Move::Pass);
+        }
+        let side = game.current_turn();
+        let empties = game.board().count(Disc::Empty);
+        let size_mb = if self.level == CpuLevel::Hard { self.config.tt_size_mb } else { 0 };
+        let table = self.table.get_or_insert_with(|| TranspositionTable::new(size_mb));
+        let ((row, col), result) = match clock.budget(side, empties) {
+            Some(budget) => {
+                let mut config = self.config.clone();
+                config.limits.time = Some(budget);
+                crate::cpu::get_best_move_and_result(game, side, self.level, &config, table)
+            }
+            None => crate::cpu::get_best_move_and_result(game, side, self.level, &self.config, table),
+        };
+        self.last_eval = result.map(|result| result.disc_margin());
+        Some(Move::Place(row, col))
     }
 
-    
+    fn last_eval(&self) -> Option<f64> {
+        self.last_eval
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
     #[test]
-    fn test_player_new() {
-        let player = Player::new(PlayerType::Human, Disc::Black);
-        match player.player_type() {
-            PlayerType::Human => assert!(true),
-            _ => assert!(false),
-        }
+    fn test_cpu_player() {
+        let player = CpuPlayer::new(CpuLevel::Easy);
+        assert_eq!(player.level(), CpuLevel::Easy);
+        assert_eq!(player.name(), "CPU (Easy)");
     }
 
     #[test]
-    fn test_cpu_player() {
-        let player = Player::new(PlayerType::Cpu(CpuLevel::Easy), Disc::White);
-        match player.player_type() {
-            PlayerType::Cpu(CpuLevel::Easy) => assert!(true),
-            _ => assert!(false),
-        }
+    fn test_cpu_player_moves() {
+        let mut player = CpuPlayer::new(CpuLevel::Hard).with_config(CpuConfig { book: None, ..CpuConfig::default() });
+        let game = Game::new();
+        let mv = player.choose_move(&game, &Clock::per_move(Duration::from_millis(50))).unwrap();
+        assert!(game.legal_moves().contains(&mv));
+        assert!(player.last_eval().is_some());
+
+        // Book moves come without an evaluation.
+        let mut player = CpuPlayer::new(CpuLevel::Hard);
+        player.choose_move(&game, &Clock::unlimited()).unwrap();
+        assert_eq!(player.last_eval(), None);
+    }
+
+    #[test]
+    fn test_cpu_player_keeps_table_between_moves() {
+        let config = CpuConfig { book: None, tt_size_mb: 1, ..CpuConfig::default() };
+        let mut player = CpuPlayer::new(CpuLevel::Hard).with_config(config);
+        let mut game = Game::new();
+        let clock = Clock::per_move(Duration::from_millis(50));
+        let mv = player.choose_move(&game, &clock).unwrap();
+        let capacity = player.table.as_ref().map(TranspositionTable::capacity);
+        assert_eq!(capacity, Some(TranspositionTable::new(1).capacity()));
+
+        // The reply searched by the first move is still in the table for the next.
+        game.play(mv);
+        game.play(game.legal_moves()[0]);
+        let key = game.board().hash(game.current_turn());
+        assert!(player.table.as_ref().unwrap().probe(key).is_some());
+        player.choose_move(&game, &clock).unwrap();
+        assert_eq!(player.table.as_ref().map(TranspositionTable::capacity), capacity);
+    }
+
+    #[test]
+    fn test_cpu_player_passes_and_stops() {
+        let mut player = CpuPlayer::new(CpuLevel::Medium);
+        // Black has no move; White can take b1 by playing c1.
+        let game = Game::from_position_str(&format!("OX{} X", "-".repeat(62))).unwrap();
+        assert_eq!(player.choose_move(&game, &Clock::unlimited()), Some(Move::Pass));
+
+        let finished = Game::from_position_str(&format!("{} X", "X".repeat(64))).unwrap();
+        assert_eq!(player.choose_move(&finished, &Clock::unlimited()), None);
     }
 
     #[test]
     fn test_cpu_level_debug() {
         let easy = CpuLevel::Easy;
         assert_eq!(format!("{:?}", easy), "Easy");
-        
+
         let medium = CpuLevel::Medium;
         assert_eq!(format!("{:?}", medium), "Medium");
-        
+
         let hard = CpuLevel::Hard;
         assert_eq!(format!("{:?}", hard), "Hard");
     }
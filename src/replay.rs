@@ -0,0 +1,134 @@
+//! Stepping back and forth through a game record, for reviewing finished games.
+
+use crate::game::{Game, MoveRecord};
+
+/// A game record with one of its positions in view. Ply 0 is the start
+/// position and ply `n` the position after the first `n` moves, passes included.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    // At the viewed ply, with the rest of the record on its redo stack.
+    game: Game,
+    moves: Vec<MoveRecord>,
+    // Black and White disc counts at each ply.
+    counts: Vec<(u32, u32)>,
+}
+
+impl Replay {
+    /// Replays the history of `game`, starting from its first position. Moves
+    /// that were taken back in `game` are not part of the record.
+    pub fn new(game: &Game) -> Self {
+        let mut game = game.clone();
+        let moves = game.history().to_vec();
+        let mut counts = vec![game.count_discs()];
+        while game.undo() {
+            counts.push(game.count_discs());
+        }
+        counts.reverse();
+        Replay { game, moves, counts }
+    }
+
+    /// The game at the viewed ply.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn ply(&self) -> usize {
+        self.game.history().len()
+    }
+
+    /// The number of moves in the record.
+    pub fn plies(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn moves(&self) -> &[MoveRecord] {
+        &self.moves
+    }
+
+    /// Black and White disc counts at `ply`.
+    pub fn counts(&self, ply: usize) -> (u32, u32) {
+        self.counts[ply.min(self.plies())]
+    }
+
+    /// The move that led to the viewed position, if any.
+    pub fn last_move(&self) -> Option<MoveRecord> {
+        self.game.history().last().copied()
+    }
+
+    /// Steps one move forward. Returns `false` at the end of the record.
+    pub fn forward(&mut self) -> bool {
+        self.ply() < self.plies() && self.game.redo()
+    }
+
+    /// Steps one move back. Returns `false` at the start.
+    pub fn back(&mut self) -> bool {
+        self.game.undo()
+    }
+
+    /// Shows the position after `ply` moves, or the end of the record if it has
+    /// fewer.
+    pub fn jump(&mut self, ply: usize) {
+        let ply = ply.min(self.plies());
+        while self.ply() < ply && self.game.redo() {}
+        while self.ply() > ply && self.game.undo() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, Disc};
+    use crate::game::Move;
+
+    #[test]
+    fn test_step_through() {
+        let mut replay = Replay::new(&Game::from_transcript("f5d6c3").unwrap());
+        assert_eq!((replay.ply(), replay.plies()), (0, 3));
+        assert_eq!(replay.game().board(), &Board::new());
+        assert_eq!(replay.last_move(), None);
+        assert!(!replay.back());
+
+        assert!(replay.forward());
+        assert_eq!(replay.last_move().map(|record| record.mv), Some("f5".parse().unwrap()));
+        assert_eq!(replay.game().count_discs(), replay.counts(1));
+        assert!(replay.forward() && replay.forward());
+        assert!(!replay.forward());
+        assert_eq!(replay.game().transcript(), "f5d6c3");
+        assert_eq!(replay.counts(0), (2, 2));
+        assert_eq!(replay.counts(3), (5, 2));
+    }
+
+    #[test]
+    fn test_jump() {
+        let game = Game::from_transcript("f5d6c3d3c4").unwrap();
+        let mut replay = Replay::new(&game);
+        replay.jump(4);
+        assert_eq!(replay.game().transcript(), "f5d6c3d3");
+        replay.jump(1);
+        assert_eq!(replay.game().transcript(), "f5");
+        replay.jump(99);
+        assert_eq!(replay.ply(), 5);
+        assert_eq!(replay.game().board(), game.board());
+        replay.jump(0);
+        assert_eq!(replay.ply(), 0);
+    }
+
+    #[test]
+    fn test_passes_and_undone_moves() {
+        // Black must pass before White's c1.
+        let mut game = Game::from_position_str(&format!("OX{} X", "-".repeat(62))).unwrap();
+        game.pass();
+        game.play("c1".parse().unwrap());
+        let mut replay = Replay::new(&game);
+        assert_eq!(replay.moves()[0].mv, Move::Pass);
+        replay.jump(2);
+        assert!(replay.game().is_game_over());
+
+        // A move that was taken back is not replayed.
+        game.undo();
+        let mut replay = Replay::new(&game);
+        replay.jump(2);
+        assert_eq!(replay.ply(), 1);
+        assert_eq!(replay.game().current_turn(), Disc::White);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::clock::{Clock, TimeControl};
+use crate::board::Disc;
+use crate::game::{Game, Move};
+use crate::player::{CpuLevel, PlayerType};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Version written by [`SavedGame::write`]. Readers keep accepting every older version.
+pub const SAVE_FORMAT_VERSION: u32 = 2;
+pub const SAVE_EXTENSION: &str = "save";
+
+const MAGIC: &str = "othello-save";
+
+/// A game in progress together with who plays each side, the time each side
+/// has left and the CPU settings it was played with.
+///
+/// On disk this is a small text file:
+///
+/// ```text
+/// othello-save 2
+/// black human
+/// white cpu hard
+/// start ---------------------------OX------XO--------------------------- X
+/// moves f5d6c3pa
+/// clock total 300 2 281.5 290.25
+/// cpu --time
+/// cpu 5
+/// ```
+///
+/// `clock` is absent for untimed games; `total` gives the time per side, the
+/// increment and the time Black and White have left, in seconds, and `per-move`
+/// the time per move. Each `cpu` line holds one word of the CPU options, as given
+/// on the command line. Version 1 files have neither. Unknown keys are ignored so
+/// newer files stay readable where possible.
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub game: Game,
+    pub black: PlayerType,
+    pub white: PlayerType,
+    pub clock: Clock,
+    /// CPU options in command-line form, e.g. `["--time", "5"]`.
+    pub cpu_options: Vec<String>,
+}
+
+impl SavedGame {
+    pub fn new(game: Game, black: PlayerType, white: PlayerType) -> Self {
+        SavedGame { game, black, white, clock: Clock::unlimited(), cpu_options: Vec::new() }
+    }
+
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_cpu_options(mut self, cpu_options: Vec<String>) -> Self {
+        self.cpu_options = cpu_options;
+        self
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (board, side) = self.game.start_position();
+        let moves: String = self
+            .game
+            .history()
+            .iter()
+            .map(|record| match record.mv {
+                Move::Pass => "pa".to_string(),
+                mv => mv.to_string(),
+            })
+            .collect();
+
+        writeln!(writer, "{} {}", MAGIC, SAVE_FORMAT_VERSION)?;
+        writeln!(writer, "black {}", format_player(self.black))?;
+        writeln!(writer, "white {}", format_player(self.white))?;
+        writeln!(writer, "start {}", Game::from_position(board, side).position_string())?;
+        writeln!(writer, "moves {}", moves)?;
+        match self.clock.control() {
+            TimeControl::Unlimited => {}
+            TimeControl::PerMove(time) => writeln!(writer, "clock per-move {}", time.as_secs_f64())?,
+            TimeControl::Total { time, increment } => {
+                let left = |side| self.clock.remaining(side).unwrap_or_default().as_secs_f64();
+                writeln!(
+                    writer,
+                    "clock total {} {} {} {}",
+                    time.as_secs_f64(),
+                    increment.as_secs_f64(),
+                    left(Disc::Black),
+                    left(Disc::White)
+                )?;
+            }
+        }
+        for option in &self.cpu_options {
+            writeln!(writer, "cpu {}", option)?;
+        }
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> io::Result<SavedGame> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or("");
+        let version = match header.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [MAGIC, version] => version.parse::<u32>().map_err(|_| invalid_data("invalid save version"))?,
+            _ => return Err(invalid_data("not an othello save file")),
+        };
+        match version {
+            1 => parse_v1(lines),
+            2 => parse_v2(lines),
+            _ => Err(invalid_data(&format!("unsupported save version {}", version))),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::File::create(path)?;
+        self.write(&mut file)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<SavedGame> {
+        SavedGame::parse(&fs::read_to_string(path)?)
+    }
+}
+
+fn parse_v1<'a>(lines: impl Iterator<Item = &'a str>) -> io::Result<SavedGame> {
+    let (mut black, mut white) = (None, None);
+    let mut game = Game::new();
+    let mut moves = "";
+    for line in lines {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "black" => black = Some(parse_player(value)?),
+            "white" => white = Some(parse_player(value)?),
+            "start" => game = Game::from_position_str(value).map_err(|e| invalid_data(&e.to_string()))?,
+            "moves" => moves = value,
+            _ => {}
+        }
+    }
+    game.play_transcript(moves).map_err(|e| invalid_data(&e.to_string()))?;
+
+    let black = black.ok_or_else(|| invalid_data("missing black player"))?;
+    let white = white.ok_or_else(|| invalid_data("missing white player"))?;
+    Ok(SavedGame::new(game, black, white))
+}
+
+// Version 2 adds the clock and the CPU options to version 1.
+fn parse_v2<'a>(lines: impl Iterator<Item = &'a str>) -> io::Result<SavedGame> {
+    let lines: Vec<&str> = lines.collect();
+    let mut saved = parse_v1(lines.iter().copied())?;
+    for line in lines {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "clock" => saved.clock = parse_clock(value)?,
+            "cpu" => saved.cpu_options.push(value.to_string()),
+            _ => {}
+        }
+    }
+    Ok(saved)
+}
+
+fn parse_clock(text: &str) -> io::Result<Clock> {
+    let invalid = || invalid_data(&format!("invalid clock '{}'", text));
+    let seconds = |value: &str| value.parse().ok().and_then(|value| Duration::try_from_secs_f64(value).ok());
+    let times: Vec<Duration> = text.split_whitespace().skip(1).map(seconds).collect::<Option<_>>().ok_or_else(invalid)?;
+    match (text.split_whitespace().next(), times.as_slice()) {
+        (Some("per-move"), &[time]) => Ok(Clock::per_move(time)),
+        (Some("total"), &[time, increment, black, white]) => {
+            let mut clock = Clock::total(time, increment);
+            clock.set_remaining(Disc::Black, black);
+            clock.set_remaining(Disc::White, white);
+            Ok(clock)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn format_player(player: PlayerType) -> &'static str {
+    match player {
+        PlayerType::Human => "human",
+        PlayerType::Cpu(CpuLevel::Easy) => "cpu easy",
+        PlayerType::Cpu(CpuLevel::Medium) => "cpu medium",
+        PlayerType::Cpu(CpuLevel::Hard) => "cpu hard",
+    }
+}
+
+fn parse_player(text: &str) -> io::Result<PlayerType> {
+    match text.trim() {
+        "human" => Ok(PlayerType::Human),
+        "cpu easy" => Ok(PlayerType::Cpu(CpuLevel::Easy)),
+        "cpu medium" => Ok(PlayerType::Cpu(CpuLevel::Medium)),
+        "cpu hard" => Ok(PlayerType::Cpu(CpuLevel::Hard)),
+        other => Err(invalid_data(&format!("unknown player '{}'", other))),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Save files in `dir`, most recently modified first. A missing directory has no saves.
+pub fn list_saves(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut saves = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == SAVE_EXTENSION) {
+            let modified = fs::metadata(&path)?.modified()?;
+            saves.push((modified, path));
+        }
+    }
+    saves.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    Ok(saves.into_iter().map(|(_, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut game = Game::from_transcript("f5d6c3d3c4").unwrap();
+        game.undo();
+        let saved = SavedGame::new(game.clone(), PlayerType::Human, PlayerType::Cpu(CpuLevel::Hard));
+
+        let mut out = Vec::new();
+        saved.write(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text,
+            "othello-save 2\nblack human\nwhite cpu hard\n\
+             start ---------------------------OX------XO--------------------------- X\n\
+             moves f5d6c3d3\n"
+        );
+
+        let loaded = SavedGame::parse(&text).unwrap();
+        assert_eq!(loaded.game.transcript(), "f5d6c3d3");
+        assert_eq!(*loaded.game.board(), *game.board());
+        assert_eq!(loaded.game.current_turn(), Disc::Black);
+        assert_eq!(loaded.black, PlayerType::Human);
+        assert_eq!(loaded.white, PlayerType::Cpu(CpuLevel::Hard));
+    }
+
+    #[test]
+    fn test_round_trip_clock_and_cpu_options() {
+        let mut clock = Clock::total(Duration::from_secs(300), Duration::from_secs(2));
+        clock.set_remaining(Disc::Black, Duration::from_millis(281_500));
+        let options = vec!["--time".to_string(), "5".to_string(), "--book".to_string(), "my book.txt".to_string()];
+        let saved = SavedGame::new(Game::from_transcript("f5").unwrap(), PlayerType::Human, PlayerType::Human)
+            .with_clock(clock.clone())
+            .with_cpu_options(options.clone());
+        let mut out = Vec::new();
+        saved.write(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("\nclock total 300 2 281.5 300\ncpu --time\ncpu 5\n"), "{}", text);
+
+        let loaded = SavedGame::parse(&text).unwrap();
+        assert_eq!(loaded.clock, clock);
+        assert_eq!(loaded.cpu_options, options);
+        let per_move = SavedGame::parse("othello-save 2\nblack human\nwhite human\nclock per-move 1.5\n").unwrap();
+        assert_eq!(per_move.clock, Clock::per_move(Duration::from_millis(1500)));
+        let error = SavedGame::parse("othello-save 2\nblack human\nwhite human\nclock total 300\n").unwrap_err();
+        assert_eq!(error.to_string(), "invalid clock 'total 300'");
+    }
+
+    #[test]
+    fn test_custom_start_position() {
+        let text = "othello-save 1\nwhite human\nblack cpu easy\n\
+                    start ---------------------------OX------XXX-------------------------- O\nmoves d6\n";
+        let loaded = SavedGame::parse(text).unwrap();
+        assert_eq!(loaded.game.history().len(), 1);
+        assert_eq!(loaded.game.current_turn(), Disc::Black);
+        assert_eq!(loaded.black, PlayerType::Cpu(CpuLevel::Easy));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_ignored() {
+        let text = "othello-save 1\nblack human\nwhite human\nclock 300\nmoves f5\n";
+        assert_eq!(SavedGame::parse(text).unwrap().game.transcript(), "f5");
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        let error = SavedGame::parse("hello\n").unwrap_err();
+        assert_eq!(error.to_string(), "not an othello save file");
+        let error = SavedGame::parse("othello-save 99\n").unwrap_err();
+        assert_eq!(error.to_string(), "unsupported save version 99");
+        let error = SavedGame::parse("othello-save 1\nblack human\nwhite robot\n").unwrap_err();
+        assert_eq!(error.to_string(), "unknown player 'robot'");
+        let error = SavedGame::parse("othello-save 1\nblack human\nwhite human\nmoves f5a1\n").unwrap_err();
+        assert_eq!(error.to_string(), "illegal move a1 at ply 2");
+    }
+
+    #[test]
+    fn test_save_load_and_list() {
+        let dir = std::env::temp_dir().join(format!("othello-save-test-{}", std::process::id()));
+        let path = dir.join("game.save");
+        let saved = SavedGame::new(Game::from_transcript("f5f6").unwrap(), PlayerType::Human, PlayerType::Human);
+        saved.save(&path).unwrap();
+        fs::write(dir.join("notes.txt"), "not a save").unwrap();
+
+        assert_eq!(list_saves(&dir).unwrap(), vec![path.clone()]);
+        assert_eq!(SavedGame::load(&path).unwrap().game.transcript(), "f5f6");
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(list_saves(&dir).unwrap().is_empty());
+    }
+}
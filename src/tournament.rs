@@ -0,0 +1,286 @@
+//! Round-robin and gauntlet tournaments, played in parallel.
+
+use crate::arena::{self, GameOutcome, MatchStats};
+use crate::board::Disc;
+use crate::clock::Clock;
+use crate::game::Game;
+use crate::player::Player;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+
+/// Creates a fresh player for each game, so games can run on any thread.
+pub type PlayerFactory = Arc<dyn Fn() -> Result<Box<dyn Player>, String> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Entrant {
+    pub name: String,
+    pub make: PlayerFactory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every entrant plays every other.
+    RoundRobin,
+    /// The first entrant plays each of the others.
+    Gauntlet,
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentConfig {
+    pub schedule: Schedule,
+    /// Games per pairing, alternating colours.
+    pub games: u32,
+    /// Random plies before each pair of games; every pairing uses the same openings.
+    pub opening_plies: u32,
+    pub seed: Option<u64>,
+    pub clock: Clock,
+    /// Games played at once.
+    pub jobs: usize,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        TournamentConfig {
+            schedule: Schedule::RoundRobin,
+            games: 2,
+            opening_plies: 0,
+            seed: None,
+            clock: Clock::unlimited(),
+            jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+/// One scheduled game: entrant indices and the opening to start from.
+#[derive(Debug, Clone)]
+pub struct Pairing {
+    pub black: usize,
+    pub white: usize,
+    pub opening: Game,
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentGame {
+    /// Position in the schedule.
+    pub index: usize,
+    pub black: usize,
+    pub white: usize,
+    pub outcome: GameOutcome,
+}
+
+/// Lists every game of a tournament between `entrants` players, in order.
+pub fn schedule(entrants: usize, config: &TournamentConfig) -> Vec<Pairing> {
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    let openings: Vec<Game> =
+        (0..config.games.div_ceil(2)).map(|_| arena::random_opening(config.opening_plies, &mut rng)).collect();
+
+    let pairs: Vec<(usize, usize)> = match config.schedule {
+        Schedule::RoundRobin => (0..entrants).flat_map(|i| (i + 1..entrants).map(move |j| (i, j))).collect(),
+        Schedule::Gauntlet => (1..entrants).map(|j| (0, j)).collect(),
+    };
+    let mut pairings = Vec::new();
+    for (i, j) in pairs {
+        for game in 0..config.games as usize {
+            let (black, white) = if game % 2 == 0 { (i, j) } else { (j, i) };
+            pairings.push(Pairing { black, white, opening: openings[game / 2].clone() });
+        }
+    }
+    pairings
+}
+
+/// Plays the whole schedule on `config.jobs` threads. `on_game` is called on the
+/// calling thread as each game finishes, in completion order. An entrant whose
+/// player cannot be created forfeits the game.
+pub fn run(entrants: &[Entrant], config: &TournamentConfig, mut on_game: impl FnMut(&TournamentGame)) -> TournamentResults {
+    let pairings = schedule(entrants.len(), config);
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut games = Vec::with_capacity(pairings.len());
+
+    thread::scope(|scope| {
+        for _ in 0..config.jobs.clamp(1, pairings.len().max(1)) {
+            let sender = sender.clone();
+            let (pairings, next) = (&pairings, &next);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(pairing) = pairings.get(index) else {
+                    break;
+                };
+                let outcome = play_pairing(entrants, pairing, &config.clock);
+                let game = TournamentGame { index, black: pairing.black, white: pairing.white, outcome };
+                if sender.send(game).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        for game in receiver {
+            on_game(&game);
+            games.push(game);
+        }
+    });
+
+    games.sort_by_key(|game| game.index);
+    TournamentResults { names: entrants.iter().map(|entrant| entrant.name.clone()).collect(), games }
+}
+
+fn play_pairing(entrants: &[Entrant], pairing: &Pairing, clock: &Clock) -> GameOutcome {
+    let game = pairing.opening.clone();
+    let mut black = match (entrants[pairing.black].make)() {
+        Ok(player) => player,
+        Err(_) => return arena::forfeit(game, Disc::Black),
+    };
+    let mut white = match (entrants[pairing.white].make)() {
+        Ok(player) => player,
+        Err(_) => return arena::forfeit(game, Disc::White),
+    };
+    arena::play_game(game, black.as_mut(), white.as_mut(), clock)
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentResults {
+    pub names: Vec<String>,
+    /// Every game, in schedule order.
+    pub games: Vec<TournamentGame>,
+}
+
+/// One line of the rating list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub entrant: usize,
+    pub stats: MatchStats,
+    /// Elo relative to the field average.
+    pub rating: f64,
+}
+
+impl TournamentResults {
+    /// `table[i][j]` is entrant `i`'s record against entrant `j`.
+    pub fn crosstable(&self) -> Vec<Vec<MatchStats>> {
+        let n = self.names.len();
+        let mut table = vec![vec![MatchStats::default(); n]; n];
+        for game in &self.games {
+            let margin = game.outcome.result.margin(Disc::Black);
+            table[game.black][game.white].record(margin);
+            table[game.white][game.black].record(-margin);
+        }
+        table
+    }
+
+    /// Entrants by points, then rating, best first.
+    pub fn standings(&self) -> Vec<Standing> {
+        let table = self.crosstable();
+        let ratings = ratings(&table);
+        let mut standings: Vec<Standing> = table
+            .iter()
+            .enumerate()
+            .map(|(entrant, row)| {
+                let mut stats = MatchStats::default();
+                for cell in row {
+                    stats.wins += cell.wins;
+                    stats.draws += cell.draws;
+                    stats.losses += cell.losses;
+                    stats.margin_total += cell.margin_total;
+                }
+                Standing { entrant, stats, rating: ratings[entrant] }
+            })
+            .collect();
+        standings.sort_by(|a, b| {
+            let points = |s: &Standing| s.stats.wins * 2 + s.stats.draws;
+            points(b).cmp(&points(a)).then(b.rating.total_cmp(&a.rating))
+        });
+        standings
+    }
+}
+
+fn points(stats: &MatchStats) -> f64 {
+    stats.wins as f64 + stats.draws as f64 / 2.0
+}
+
+/// Bradley-Terry ratings by minorization-maximization. Every entrant also gets a
+/// virtual draw against an average opponent, which keeps ratings finite when
+/// someone wins or loses every game.
+fn ratings(table: &[Vec<MatchStats>]) -> Vec<f64> {
+    let n = table.len();
+    let mut strength = vec![1.0f64; n];
+    for _ in 0..1000 {
+        let previous = strength.clone();
+        for i in 0..n {
+            let mut denominator = 1.0 / (previous[i] + 1.0);
+            for j in 0..n {
+                let games = table[i][j].games() as f64;
+                if games > 0.0 {
+                    denominator += games / (previous[i] + previous[j]);
+                }
+            }
+            let wins: f64 = table[i].iter().map(points).sum::<f64>() + 0.5;
+            strength[i] = wins / denominator;
+        }
+        let converged = strength.iter().zip(&previous).all(|(a, b)| (a - b).abs() < 1e-9 * b.max(1e-9));
+        if converged {
+            break;
+        }
+    }
+    let elo: Vec<f64> = strength.iter().map(|s| 400.0 * s.log10()).collect();
+    let mean = elo.iter().sum::<f64>() / n.max(1) as f64;
+    elo.iter().map(|rating| rating - mean).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::{CpuConfig, SearchLimits};
+    use crate::player::{CpuLevel, CpuPlayer};
+
+    fn entrant(level: CpuLevel) -> Entrant {
+        let make: PlayerFactory = Arc::new(move || {
+            let config = CpuConfig { limits: SearchLimits::depth(2), book: None, seed: Some(5), ..CpuConfig::default() };
+            Ok(Box::new(CpuPlayer::new(level).with_config(config)) as Box<dyn Player>)
+        });
+        Entrant { name: format!("{:?}", level), make }
+    }
+
+    fn broken() -> Entrant {
+        Entrant { name: "broken".to_string(), make: Arc::new(|| Err("cannot start".to_string())) }
+    }
+
+    #[test]
+    fn test_schedules() {
+        let config = TournamentConfig { games: 2, seed: Some(1), ..TournamentConfig::default() };
+        let round_robin = schedule(4, &config);
+        assert_eq!(round_robin.len(), 6 * 2);
+        assert_eq!((round_robin[0].black, round_robin[0].white), (0, 1));
+        assert_eq!((round_robin[1].black, round_robin[1].white), (1, 0));
+
+        let gauntlet = schedule(4, &TournamentConfig { schedule: Schedule::Gauntlet, ..config });
+        assert_eq!(gauntlet.len(), 3 * 2);
+        assert!(gauntlet.iter().all(|pairing| pairing.black == 0 || pairing.white == 0));
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let entrants = [entrant(CpuLevel::Hard), entrant(CpuLevel::Easy), broken()];
+        let config = TournamentConfig { games: 2, opening_plies: 2, seed: Some(4), jobs: 3, ..TournamentConfig::default() };
+        let mut finished = 0;
+        let results = run(&entrants, &config, |_| finished += 1);
+        assert_eq!(finished, 6);
+        assert!(results.games.iter().enumerate().all(|(index, game)| game.index == index));
+
+        let table = results.crosstable();
+        assert_eq!(table[0][2].wins, 2);
+        assert_eq!(table[2][1].losses, 2);
+        assert_eq!(table[0][1].games(), 2);
+
+        let standings = results.standings();
+        assert_eq!(standings.last().unwrap().entrant, 2);
+        assert!(standings[0].rating > standings[2].rating);
+        let total: f64 = standings.iter().map(|standing| standing.rating).sum();
+        assert!(total.abs() < 1e-6);
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+use std::mem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The true score is at least `score` (the search failed high).
+    Lower,
+    /// The true score is at most `score` (the search failed low).
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    pub key: u64,
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    /// Square index (`row * 8 + col`) of the best move found, if any.
+    pub best_move: Option<u8>,
+}
+
+/// Fixed-size, always-replace-if-deeper hash table of search results keyed by
+/// the Zobrist hash of the position.
+#[derive(Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+}
+
+impl fmt::Debug for TranspositionTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranspositionTable").field("capacity", &self.capacity()).finish()
+    }
+}
+
+impl TranspositionTable {
+    /// Creates a table using at most `size_mb` megabytes (and at least one entry).
+    pub fn new(size_mb: usize) -> Self {
+        let entry_size = mem::size_of::<Option<TtEntry>>();
+        let max_entries = (size_mb * 1024 * 1024 / entry_size).max(1);
+        // Round down to a power of two so the index is a simple mask.
+        let capacity = 1 << (usize::BITS - 1 - max_entries.leading_zeros());
+        TranspositionTable { entries: vec![None; capacity] }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    pub fn store(&mut self, entry: TtEntry) {
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+        let replace = match slot {
+            Some(existing) => existing.key != entry.key || entry.depth >= existing.depth,
+            None => true,
+        };
+        if replace {
+            *slot = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, depth: u8, score: i32) -> TtEntry {
+        TtEntry { key, depth, score, bound: Bound::Exact, best_move: Some(19) }
+    }
+
+    #[test]
+    fn test_capacity_is_power_of_two_within_budget() {
+        let table = TranspositionTable::new(1);
+        assert!(table.capacity().is_power_of_two());
+        assert!(table.capacity() * mem::size_of::<Option<TtEntry>>() <= 1024 * 1024);
+        assert_eq!(TranspositionTable::new(0).capacity(), 1);
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(table.probe(42), None);
+        table.store(entry(42, 3, 10));
+        assert_eq!(table.probe(42), Some(entry(42, 3, 10)));
+
+        // A different key mapping to the same slot is not reported as a hit.
+        let colliding = 42 + table.capacity() as u64;
+        assert_eq!(table.probe(colliding), None);
+    }
+
+    #[test]
+    fn test_shallower_result_does_not_replace_same_position() {
+        let mut table = TranspositionTable::new(1);
+        table.store(entry(7, 5, 10));
+        table.store(entry(7, 2, 99));
+        assert_eq!(table.probe(7).unwrap().depth, 5);
+        table.store(entry(7, 6, -3));
+        assert_eq!(table.probe(7).unwrap().score, -3);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut table = TranspositionTable::new(1);
+        table.store(entry(1, 1, 1));
+        table.clear();
+        assert_eq!(table.probe(1), None);
+    }
+}
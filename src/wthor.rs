@@ -0,0 +1,356 @@
+//! WTHOR game databases, the binary format used by the French Othello federation
+//! archives.
+//!
+//! A database is a `.wtb` file of games, with player and tournament names in
+//! separate `.JOU` and `.TRN` files. Every file starts with a 16-byte header;
+//! integers are little-endian and names are Latin-1.
+
+use crate::board::{Board, Disc};
+use crate::game::{Game, Move};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const HEADER_SIZE: usize = 16;
+pub const GAME_RECORD_SIZE: usize = 68;
+const MOVES_PER_RECORD: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// Today's date in UTC.
+    pub fn today() -> Date {
+        let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() / 86_400);
+        // Civil date from days since 1970-01-01, counting years from March.
+        let days = days as i64 + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Date { year: year as u16, month: month as u8, day: day as u8 }
+    }
+}
+
+/// Header fields of a `.wtb` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WthorHeader {
+    pub created: Date,
+    /// Year the games were played.
+    pub year: u16,
+    /// Depth at which the theoretical scores were computed.
+    pub depth: u8,
+}
+
+/// One game of a database. Player and tournament fields are indices into the
+/// name files.
+#[derive(Debug, Clone)]
+pub struct WthorGame {
+    pub tournament: u16,
+    pub black: u16,
+    pub white: u16,
+    /// Black's final disc count, with empty squares going to the winner.
+    pub black_discs: u8,
+    /// Black's disc count under perfect play from `depth` empties, as computed by
+    /// the archive.
+    pub theoretical_discs: u8,
+    pub game: Game,
+}
+
+impl WthorGame {
+    /// Wraps a game from the initial position. Both scores are taken from the
+    /// final position; an unfinished game scores its current disc count.
+    pub fn new(game: Game, tournament: u16, black: u16, white: u16) -> Self {
+        let black_discs = game.result().map_or(game.board().count(Disc::Black), |result| result.black) as u8;
+        WthorGame { tournament, black, white, black_discs, theoretical_discs: black_discs, game }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameDatabase {
+    pub header: WthorHeader,
+    pub games: Vec<WthorGame>,
+}
+
+impl GameDatabase {
+    /// An empty database of games played in `year`, created today.
+    pub fn new(year: u16) -> Self {
+        GameDatabase { header: WthorHeader { created: Date::today(), year, depth: 0 }, games: Vec::new() }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<GameDatabase> {
+        GameDatabase::read(&mut fs::File::open(path)?)
+    }
+
+    /// Reads a `.wtb` file. Passes are not recorded in WTHOR and are inserted
+    /// where forced; an illegal move is an error.
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<GameDatabase> {
+        let (created, header) = read_header(reader)?;
+        let count = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if !matches!(header[12], 0 | 8) {
+            return Err(invalid_data(format!("unsupported board size {}", header[12])));
+        }
+        let mut database = GameDatabase {
+            header: WthorHeader { created, year: u16::from_le_bytes([header[10], header[11]]), depth: header[14] },
+            games: Vec::with_capacity(count.min(1 << 20)),
+        };
+        let mut record = [0u8; GAME_RECORD_SIZE];
+        for index in 0..count {
+            reader.read_exact(&mut record).map_err(|e| truncated(e, "game", index))?;
+            let game =
+                decode_moves(&record[8..]).map_err(|message| invalid_data(format!("game {}: {}", index + 1, message)))?;
+            database.games.push(WthorGame {
+                tournament: u16::from_le_bytes([record[0], record[1]]),
+                black: u16::from_le_bytes([record[2], record[3]]),
+                white: u16::from_le_bytes([record[4], record[5]]),
+                black_discs: record[6],
+                theoretical_discs: record[7],
+                game,
+            });
+        }
+        Ok(database)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        self.write(&mut file)
+    }
+
+    /// Writes a `.wtb` file. Every game must start from the initial position.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut header = header_bytes(self.header.created);
+        header[4..8].copy_from_slice(&(self.games.len() as u32).to_le_bytes());
+        header[10..12].copy_from_slice(&self.header.year.to_le_bytes());
+        header[12] = 8;
+        header[14] = self.header.depth;
+        writer.write_all(&header)?;
+        for (index, game) in self.games.iter().enumerate() {
+            if game.game.start_position() != (Board::new(), Disc::Black) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("game {}: WTHOR games must start from the initial position", index + 1),
+                ));
+            }
+            let mut record = [0u8; GAME_RECORD_SIZE];
+            record[0..2].copy_from_slice(&game.tournament.to_le_bytes());
+            record[2..4].copy_from_slice(&game.black.to_le_bytes());
+            record[4..6].copy_from_slice(&game.white.to_le_bytes());
+            record[6] = game.black_discs;
+            record[7] = game.theoretical_discs;
+            let squares = game.game.history().iter().filter_map(|record| match record.mv {
+                Move::Place(row, col) => Some((10 * (row + 1) + col + 1) as u8),
+                Move::Pass => None,
+            });
+            for (slot, square) in record[8..].iter_mut().zip(squares) {
+                *slot = square;
+            }
+            writer.write_all(&record)?;
+        }
+        Ok(())
+    }
+}
+
+// Moves are stored as 10 * row + column, both counted from 1; 0 ends the game.
+fn decode_moves(bytes: &[u8]) -> Result<Game, String> {
+    let mut game = Game::new();
+    for (ply, &square) in bytes.iter().take(MOVES_PER_RECORD).enumerate() {
+        if square == 0 {
+            break;
+        }
+        let (row, col) = (square as usize / 10, square as usize % 10);
+        if !(1..=8).contains(&row) || !(1..=8).contains(&col) {
+            return Err(format!("invalid square {} at move {}", square, ply + 1));
+        }
+        if game.must_pass() {
+            game.pass();
+        }
+        let mv = Move::Place(row - 1, col - 1);
+        if !game.play(mv) {
+            return Err(format!("illegal move {} at move {}", mv, ply + 1));
+        }
+    }
+    Ok(game)
+}
+
+/// Which kind of name file: players (`.JOU`) or tournaments (`.TRN`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameKind {
+    Players,
+    Tournaments,
+}
+
+impl NameKind {
+    pub fn record_size(self) -> usize {
+        match self {
+            NameKind::Players => 20,
+            NameKind::Tournaments => 26,
+        }
+    }
+}
+
+pub fn load_names(path: impl AsRef<Path>, kind: NameKind) -> io::Result<Vec<String>> {
+    read_names(&mut fs::File::open(path)?, kind)
+}
+
+/// Reads a name file; a game's player or tournament index looks up this list.
+pub fn read_names<R: Read>(reader: &mut R, kind: NameKind) -> io::Result<Vec<String>> {
+    let (_, header) = read_header(reader)?;
+    let count = u16::from_le_bytes([header[8], header[9]]) as usize;
+    let mut record = vec![0u8; kind.record_size()];
+    let mut names = Vec::with_capacity(count);
+    for index in 0..count {
+        reader.read_exact(&mut record).map_err(|e| truncated(e, "name", index))?;
+        let end = record.iter().position(|&byte| byte == 0).unwrap_or(record.len());
+        // Latin-1 maps each byte to the code point of the same value.
+        names.push(record[..end].iter().map(|&byte| byte as char).collect::<String>().trim_end().to_string());
+    }
+    Ok(names)
+}
+
+/// Writes a name file. Names are cut to fit the record, and characters outside
+/// Latin-1 become `?`.
+pub fn write_names<W: Write>(writer: &mut W, kind: NameKind, names: &[String]) -> io::Result<()> {
+    let count = u16::try_from(names.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many names for a WTHOR file"))?;
+    let mut header = header_bytes(Date::today());
+    header[8..10].copy_from_slice(&count.to_le_bytes());
+    writer.write_all(&header)?;
+    for name in names {
+        let mut record = vec![0u8; kind.record_size()];
+        // Keep the last byte as the terminator.
+        for (slot, c) in record[..kind.record_size() - 1].iter_mut().zip(name.chars()) {
+            *slot = u8::try_from(u32::from(c)).unwrap_or(b'?');
+        }
+        writer.write_all(&record)?;
+    }
+    Ok(())
+}
+
+fn header_bytes(created: Date) -> [u8; HEADER_SIZE] {
+    let mut header = [0u8; HEADER_SIZE];
+    header[0] = (created.year / 100) as u8;
+    header[1] = (created.year % 100) as u8;
+    header[2] = created.month;
+    header[3] = created.day;
+    header
+}
+
+fn read_header<R: Read>(reader: &mut R) -> io::Result<(Date, [u8; HEADER_SIZE])> {
+    let mut header = [0u8; HEADER_SIZE];
+    reader.read_exact(&mut header).map_err(|e| truncated(e, "header", 0))?;
+    let created = Date { year: header[0] as u16 * 100 + header[1] as u16, month: header[2], day: header[3] };
+    Ok((created, header))
+}
+
+fn truncated(error: io::Error, what: &str, index: usize) -> io::Error {
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        invalid_data(format!("file ends inside {} record {}", what, index + 1))
+    } else {
+        error
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::random_opening;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn database() -> GameDatabase {
+        let mut database = GameDatabase::new(2024);
+        database.header.created = Date { year: 2024, month: 5, day: 17 };
+        // A finished 9-move wipeout and an unfinished game.
+        let wipeout = Game::from_transcript("d3c3b3d2e1d6d7e3f4").unwrap();
+        database.games.push(WthorGame::new(wipeout, 1, 2, 3));
+        database.games.push(WthorGame::new(Game::from_transcript("f5f6e6f4").unwrap(), 0, 4, 5));
+        database
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let database = database();
+        let mut bytes = Vec::new();
+        database.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + 2 * GAME_RECORD_SIZE);
+        assert_eq!(&bytes[..4], &[20, 24, 5, 17]);
+        // d3 is row 3, column 4.
+        assert_eq!(bytes[HEADER_SIZE + 8], 34);
+
+        let read = GameDatabase::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.header, database.header);
+        assert_eq!(read.games.len(), 2);
+        let first = &read.games[0];
+        assert_eq!((first.tournament, first.black, first.white), (1, 2, 3));
+        assert_eq!(u32::from(first.black_discs), first.game.result().unwrap().black);
+        assert_eq!(first.game.transcript(), "d3c3b3d2e1d6d7e3f4");
+        assert!(first.game.is_game_over());
+        assert_eq!(read.games[1].black_discs, 3);
+    }
+
+    #[test]
+    fn test_read_rejects_bad_records() {
+        let error = decode_moves(&[56, 56]).unwrap_err();
+        assert!(error.contains("illegal move f5 at move 2"), "{}", error);
+        assert!(decode_moves(&[59]).unwrap_err().contains("invalid square"));
+
+        let mut bytes = Vec::new();
+        database().write(&mut bytes).unwrap();
+        bytes.truncate(HEADER_SIZE + GAME_RECORD_SIZE + 10);
+        let error = GameDatabase::read(&mut bytes.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("game record 2"), "{}", error);
+    }
+
+    #[test]
+    fn test_passes_are_implied() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let game = std::iter::repeat_with(|| random_opening(60, &mut rng))
+            .find(|game| game.history().iter().any(|record| record.mv == Move::Pass))
+            .unwrap();
+        let mut bytes = Vec::new();
+        let mut database = GameDatabase::new(2024);
+        database.games.push(WthorGame::new(game.clone(), 0, 0, 0));
+        database.write(&mut bytes).unwrap();
+        let read = GameDatabase::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.games[0].game.transcript(), game.transcript());
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        let names: Vec<String> =
+            ["Tamenori Hideshi", "Lévy", "A very long tournament name indeed"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect();
+        let mut bytes = Vec::new();
+        write_names(&mut bytes, NameKind::Tournaments, &names).unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + 3 * 26);
+        let read = read_names(&mut bytes.as_slice(), NameKind::Tournaments).unwrap();
+        assert_eq!(read[..2], names[..2]);
+        assert_eq!(read[2], "A very long tournament na");
+
+        let mut bytes = Vec::new();
+        write_names(&mut bytes, NameKind::Players, &names[..1]).unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + 20);
+    }
+
+    #[test]
+    fn test_today_is_plausible() {
+        let today = Date::today();
+        assert!(today.year >= 2024);
+        assert!((1..=12).contains(&today.month) && (1..=31).contains(&today.day));
+    }
+}